@@ -0,0 +1,115 @@
+use chrono::{DateTime, Duration, Local};
+
+use crate::dates::{end_of_day, DueBand};
+use crate::todo_item::TodoItem;
+use crate::zone::Zone;
+
+/// # Agenda Section
+/// The items that fall in one band of an agenda.
+///
+/// ## Data Members
+///   - band: how soon the items are due, or None for items without a due
+///     date
+///   - items: the items in the section, in the order they were given
+#[derive(Clone, Debug)]
+pub struct Section<'a> {
+    pub band: Option<DueBand>,
+    pub items: Vec<&'a TodoItem>,
+}
+
+impl<'a> Section<'a> {
+    /// The heading of the section
+    pub fn title(&self) -> &'static str {
+        match self.band {
+            Some(DueBand::Overdue) => "Overdue",
+            Some(DueBand::Today) => "Today",
+            Some(DueBand::Tomorrow) => "Tomorrow",
+            Some(DueBand::ThisWeek) => "This week",
+            Some(DueBand::Later) => "Later",
+            None => "No date",
+        }
+    }
+}
+
+/// # Agenda
+/// Groups the items that aren't done or waiting into sections by how soon
+/// they are due, overdue items first and items without a due date last.
+/// Sections without any items are left out.
+///
+/// ## Args
+///   - items: the todo items, in the order they should appear in a section
+///   - now: the current time
+///   - bands: which bands to include. Items without a due date are only
+///     included if `undated` is set
+///   - until: leave out items due after this time
+///   - undated: include a section of items without a due date
+///   - zone: the timezone days are counted in
+pub fn agenda<'a>(
+    items: &'a [TodoItem],
+    now: DateTime<Local>,
+    bands: &[DueBand],
+    until: Option<DateTime<Local>>,
+    undated: bool,
+    zone: Zone,
+) -> Vec<Section<'a>> {
+    let mut sections: Vec<Section> = bands
+        .iter()
+        .map(|band| Section {
+            band: Some(*band),
+            items: Vec::new(),
+        })
+        .collect();
+    sections.sort_by_key(|section| section.band);
+    let mut no_date = Section {
+        band: None,
+        items: Vec::new(),
+    };
+
+    for item in items
+        .iter()
+        .filter(|item| !item.is_done() && !item.is_waiting(now))
+    {
+        match item.due {
+            Some(due) if until.is_none_or(|until| due <= until) => {
+                let band = DueBand::of(due, now, item.due_zone(zone));
+                if let Some(section) = sections
+                    .iter_mut()
+                    .find(|section| section.band == Some(band))
+                {
+                    section.items.push(item);
+                }
+            }
+            Some(_) => (),
+            None if undated => no_date.items.push(item),
+            None => (),
+        }
+    }
+
+    sections.push(no_date);
+    sections.retain(|section| !section.items.is_empty());
+    sections
+}
+
+/// Every band, for an agenda of everything that isn't done
+pub const ALL_BANDS: &[DueBand] = &[
+    DueBand::Overdue,
+    DueBand::Today,
+    DueBand::Tomorrow,
+    DueBand::ThisWeek,
+    DueBand::Later,
+];
+
+/// # Days From Now
+/// Gets the last moment of the day the given number of days from now, for
+/// limiting an agenda to the days coming up. Zero days is the end of today.
+///
+/// ## Args
+///   - now: the current time
+///   - days: how many days after today to include
+///   - zone: the timezone the days are in
+pub fn days_from_now(now: DateTime<Local>, days: u32, zone: Zone) -> DateTime<Local> {
+    end_of_day(
+        zone.at(now).date_naive() + Duration::days(i64::from(days)),
+        zone,
+    )
+}
@@ -0,0 +1,160 @@
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::todo_item::TodoItem;
+use crate::zone::Zone;
+
+/// # Calendar Span
+/// How much time a calendar shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Span {
+    Month,
+    Week,
+}
+
+// FromStr trait implementation so the span can be given on the command line
+impl FromStr for Span {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Span, Error> {
+        match s.to_lowercase().as_str() {
+            "month" => Ok(Span::Month),
+            "week" => Ok(Span::Week),
+            _ => Err(Error::Parse(format!(
+                "Unknown calendar span '{}'. Expected month or week",
+                s
+            ))),
+        }
+    }
+}
+
+// Display trait implementation that writes the span name back out
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// # Calendar Day
+/// A single day of a calendar and the items due on it.
+///
+/// ## Data Members
+///   - date: the day
+///   - in_span: whether the day is part of the month or week being shown,
+///     rather than padding to fill out the first or last week of a month
+///   - items: the items due that day, in the order they were given
+#[derive(Clone, Debug)]
+pub struct Day<'a> {
+    pub date: NaiveDate,
+    pub in_span: bool,
+    pub items: Vec<&'a TodoItem>,
+}
+
+impl<'a> Day<'a> {
+    /// # Highest Priority
+    /// Gets the most important priority of the items due that day. Priority
+    /// 0 is the most important.
+    pub fn highest_priority(&self) -> Option<i8> {
+        self.items.iter().filter_map(|item| item.priority).min()
+    }
+}
+
+/// # Calendar
+/// The items that aren't done laid out by the day they are due, one row per
+/// week from Monday to Sunday.
+///
+/// ## Data Members
+///   - span: whether a month or a week is shown
+///   - first: the first day of the month or week
+///   - weeks: the days shown, a week at a time
+#[derive(Clone, Debug)]
+pub struct Calendar<'a> {
+    pub span: Span,
+    pub first: NaiveDate,
+    pub weeks: Vec<Vec<Day<'a>>>,
+}
+
+impl<'a> Calendar<'a> {
+    /// # New Calendar
+    /// Lays out the month or week containing a date, moved on by a number of
+    /// months or weeks
+    ///
+    /// ## Args
+    ///   - items: the todo items to place
+    ///   - span: whether to show a month or a week
+    ///   - anchor: a day in the month or week to show
+    ///   - offset: how many months or weeks to move from the anchor, negative
+    ///     to go back
+    ///   - zone: the timezone dates are shown in. Items due all day are on
+    ///     their day in the timezone they were set in
+    pub fn new(
+        items: &'a [TodoItem],
+        span: Span,
+        anchor: NaiveDate,
+        offset: i32,
+        zone: Zone,
+    ) -> Calendar<'a> {
+        // Work out the first and last days being shown
+        let (first, last) = match span {
+            Span::Month => {
+                let month = anchor.with_day(1).unwrap();
+                let month = if offset < 0 {
+                    month - Months::new(offset.unsigned_abs())
+                } else {
+                    month + Months::new(offset as u32)
+                };
+                (month, month + Months::new(1) - Duration::days(1))
+            }
+            Span::Week => {
+                let monday = monday_of(anchor) + Duration::weeks(i64::from(offset));
+                (monday, monday + Duration::days(6))
+            }
+        };
+
+        // Fill out whole weeks from the Monday before the first day to the
+        // Sunday after the last
+        let mut weeks = Vec::new();
+        let mut date = monday_of(first);
+        while date <= last {
+            let week = (0..7)
+                .map(|day| {
+                    let date = date + Duration::days(day);
+                    Day {
+                        date,
+                        in_span: first <= date && date <= last,
+                        items: items
+                            .iter()
+                            .filter(|item| !item.is_done())
+                            .filter(|item| {
+                                item.due.is_some_and(|due| {
+                                    item.due_zone(zone).at(due).date_naive() == date
+                                })
+                            })
+                            .collect(),
+                    }
+                })
+                .collect();
+            weeks.push(week);
+            date += Duration::weeks(1);
+        }
+
+        Calendar { span, first, weeks }
+    }
+
+    /// # Title
+    /// Names the month or week shown, eg. "April 2020" or "Week of Apr 06
+    /// 2020"
+    pub fn title(&self) -> String {
+        match self.span {
+            Span::Month => self.first.format("%B %Y").to_string(),
+            Span::Week => self.first.format("Week of %b %d %Y").to_string(),
+        }
+    }
+}
+
+/// Gets the Monday on or before a date
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
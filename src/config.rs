@@ -0,0 +1,205 @@
+use chrono::Duration;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::dates::{DateDialect, DateDisplay, DateStyle};
+use crate::error::{Error, Result};
+use crate::urgency::Coefficients;
+use crate::workload;
+use crate::zone::Zone;
+
+/// # Config Object
+/// User settings read from the config file at $CONFIG/todo/config. The file
+/// has one `key = value` setting per line and lines starting with '#' are
+/// comments. Settings that aren't in the file keep their default.
+///
+/// ## Data Members
+///   - daily_capacity: how much work fits in a day (`daily_capacity = 8h`)
+///   - urgency: weights of the urgency score (`urgency.due = 12.0`,
+///     `urgency.tag.work = 2.5`)
+///   - dates: how each date column is shown (`dates.due = relative`)
+///   - auto_archive: archive items this many days after they are completed
+///     (`auto_archive = 14`). Items are only archived by hand if unset
+///   - bulk: ask before a command changes more than this many items at once
+///     (`bulk = 3`)
+///   - dialect: how dates written as numbers are read (`date_dialect = uk`)
+///   - confirm_dates: ask whether a due date was read right before saving
+///     it (`confirm_dates = true`)
+///   - timezone: the timezone dates are typed and shown in
+///     (`timezone = America/New_York`). The computer's timezone if unset
+///   - opener: the command links are opened with, which is given the link
+///     after any arguments of its own (`opener = firefox --new-tab`)
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub daily_capacity: Duration,
+    pub urgency: Coefficients,
+    pub dates: DateDisplay,
+    pub auto_archive: Option<u32>,
+    pub bulk: usize,
+    pub dialect: DateDialect,
+    pub confirm_dates: bool,
+    pub timezone: Zone,
+    pub opener: String,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            daily_capacity: Duration::hours(8),
+            urgency: Coefficients::default(),
+            dates: DateDisplay::default(),
+            auto_archive: None,
+            bulk: 3,
+            dialect: DateDialect::Us,
+            confirm_dates: false,
+            timezone: Zone::Local,
+            opener: default_opener().to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// # Default Path
+    /// Gets the default config file location, $CONFIG/todo/config
+    pub fn default_path() -> Result<PathBuf> {
+        match dirs::config_dir() {
+            Some(mut path) => {
+                path.push("todo");
+                path.push("config");
+                Ok(path)
+            }
+            None => Err(Error::NoConfigDir),
+        }
+    }
+
+    /// # Load
+    /// Reads the config file from the default location
+    pub fn load() -> Result<Config> {
+        Config::from_file(&Config::default_path()?)
+    }
+
+    /// # From File
+    /// Reads a config file. A missing file gives the default config.
+    ///
+    /// ## Args
+    ///   - path: location of the config file
+    ///
+    /// ## Rets
+    ///   - Result with the config or an error naming the bad line
+    pub fn from_file(path: &Path) -> Result<Config> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        Config::parse(&fs::read_to_string(path)?)
+    }
+
+    /// # Parse
+    /// Parses the text of a config file
+    pub fn parse(text: &str) -> Result<Config> {
+        let mut config = Config::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let bad_line =
+                |message: String| Error::Parse(format!("Config line {}: {}", number + 1, message));
+
+            let (key, value) = match line.find('=') {
+                Some(split) => (line[..split].trim(), line[split + 1..].trim()),
+                None => {
+                    return Err(bad_line(format!(
+                        "expected 'key = value' but got '{}'",
+                        line
+                    )))
+                }
+            };
+
+            match key {
+                "daily_capacity" => {
+                    config.daily_capacity =
+                        workload::parse_estimate(value).map_err(|e| bad_line(e.to_string()))?;
+                }
+                "auto_archive" => {
+                    let days = value.parse::<u32>().map_err(|_| {
+                        bad_line(format!("'{}' is not a whole number of days", value))
+                    })?;
+                    config.auto_archive = Some(days);
+                }
+                "bulk" => {
+                    config.bulk = value.parse::<usize>().map_err(|_| {
+                        bad_line(format!("'{}' is not a whole number of items", value))
+                    })?;
+                }
+                "date_dialect" => {
+                    config.dialect = value
+                        .parse::<DateDialect>()
+                        .map_err(|e| bad_line(e.to_string()))?;
+                }
+                "timezone" => {
+                    config.timezone = value.parse::<Zone>().map_err(|e| bad_line(e.to_string()))?;
+                }
+                "opener" => {
+                    if value.is_empty() {
+                        return Err(bad_line(String::from("the opener can't be empty")));
+                    }
+                    config.opener = value.to_string();
+                }
+                "confirm_dates" => {
+                    config.confirm_dates = value
+                        .parse::<bool>()
+                        .map_err(|_| bad_line(format!("'{}' is not true or false", value)))?;
+                }
+                _ if key.starts_with("urgency.") => {
+                    let weight = value
+                        .parse::<f64>()
+                        .map_err(|_| bad_line(format!("'{}' is not a number", value)))?;
+                    let urgency = &mut config.urgency;
+                    match &key["urgency.".len()..] {
+                        "priority" => urgency.priority = weight,
+                        "due" => urgency.due = weight,
+                        "overdue" => urgency.overdue = weight,
+                        "age" => urgency.age = weight,
+                        "blocking" => urgency.blocking = weight,
+                        "blocked" => urgency.blocked = weight,
+                        "tags" => urgency.tags = weight,
+                        tag if tag.starts_with("tag.") && tag.len() > 4 => {
+                            urgency.tag.insert(tag[4..].to_string(), weight);
+                        }
+                        _ => return Err(bad_line(format!("unknown setting '{}'", key))),
+                    }
+                }
+                _ if key.starts_with("dates.") => {
+                    let style = value
+                        .parse::<DateStyle>()
+                        .map_err(|e| bad_line(e.to_string()))?;
+                    match &key["dates.".len()..] {
+                        "due" => config.dates.due = style,
+                        "created" => config.dates.created = style,
+                        "completed" => config.dates.completed = style,
+                        "wait" => config.dates.wait = style,
+                        _ => return Err(bad_line(format!("unknown setting '{}'", key))),
+                    }
+                }
+                _ => return Err(bad_line(format!("unknown setting '{}'", key))),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// # Default Opener
+/// Gets the command that opens links with the program the desktop uses for
+/// them
+fn default_opener() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    }
+}
@@ -0,0 +1,479 @@
+use chrono::{DateTime, Duration, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use chrono_english::{parse_date_string, parse_duration, Dialect, Interval};
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::zone::Zone;
+
+/// # Due Band
+/// How soon a due date is, from overdue through to later than a week out.
+/// Bands are in order of urgency so they can be compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DueBand {
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+}
+
+impl DueBand {
+    /// # Band of a Due Date
+    /// Works out which band a due date falls in. This week is the next seven
+    /// days, so it always covers the same amount of time whatever day of the
+    /// week it is.
+    ///
+    /// ## Args
+    ///   - due: the due date
+    ///   - now: the current time
+    ///   - zone: the timezone days are counted in
+    pub fn of(due: DateTime<Local>, now: DateTime<Local>, zone: Zone) -> DueBand {
+        let days = (zone.at(due).date_naive() - zone.at(now).date_naive()).num_days();
+        if due < now {
+            DueBand::Overdue
+        } else if days == 0 {
+            DueBand::Today
+        } else if days == 1 {
+            DueBand::Tomorrow
+        } else if days < 7 {
+            DueBand::ThisWeek
+        } else {
+            DueBand::Later
+        }
+    }
+}
+
+/// # Date Style
+/// How a date is shown.
+///
+///   - Absolute: the date and time, eg. "04/04/20 03:00PM"
+///   - Relative: how long until or since the date, eg. "in 3h" or
+///     "tomorrow 9am"
+///   - Both: the absolute date followed by the relative one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateStyle {
+    Absolute,
+    Relative,
+    Both,
+}
+
+// FromStr trait implementation so the style can be given in the config
+// file or on the command line
+impl FromStr for DateStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DateStyle, Error> {
+        match s.to_lowercase().as_str() {
+            "absolute" => Ok(DateStyle::Absolute),
+            "relative" => Ok(DateStyle::Relative),
+            "both" => Ok(DateStyle::Both),
+            _ => Err(Error::Parse(format!(
+                "Unknown date style '{}'. Expected one of absolute, relative, both",
+                s
+            ))),
+        }
+    }
+}
+
+// Display trait implementation that writes the style name back out
+impl fmt::Display for DateStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl DateStyle {
+    /// # Format a Date
+    /// Formats a date in this style
+    ///
+    /// ## Args
+    ///   - date: the date to format
+    ///   - now: the current time, for relative dates
+    ///   - due: whether the date is a due date, so that past dates read as
+    ///     overdue rather than ago
+    pub fn format<Tz: TimeZone>(self, date: DateTime<Tz>, now: DateTime<Tz>, due: bool) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        match self {
+            DateStyle::Absolute => format_absolute(date),
+            DateStyle::Relative => format_relative(date, now, due),
+            DateStyle::Both => format!(
+                "{} ({})",
+                format_absolute(date.clone()),
+                format_relative(date, now, due)
+            ),
+        }
+    }
+
+    /// # Format a Day
+    /// Formats a day without a time in this style, for items due all day
+    ///
+    /// ## Args
+    ///   - day: the day to format
+    ///   - today: the current day, for relative dates
+    ///   - due: whether the day is a due date, so that past days read as
+    ///     overdue rather than ago
+    pub fn format_day(self, day: NaiveDate, today: NaiveDate, due: bool) -> String {
+        match self {
+            DateStyle::Absolute => format_absolute_day(day),
+            DateStyle::Relative => format_relative_day(day, today, due),
+            DateStyle::Both => format!(
+                "{} ({})",
+                format_absolute_day(day),
+                format_relative_day(day, today, due)
+            ),
+        }
+    }
+}
+
+/// # Format Absolute Date
+/// Formats a date as its day and time, eg. "04/04/20 03:00PM"
+pub fn format_absolute<Tz: TimeZone>(date: DateTime<Tz>) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    date.format("%m/%d/%y %I:%M%p").to_string()
+}
+
+/// # Format Absolute Day
+/// Formats a day without a time, eg. "04/04/20"
+pub fn format_absolute_day(day: NaiveDate) -> String {
+    day.format("%m/%d/%y").to_string()
+}
+
+/// # Format Long Date
+/// Formats a date with the month written out so that it reads the same in
+/// every dialect, eg. "Sat Apr 04 2020 03:00PM"
+pub fn format_long<Tz: TimeZone>(date: DateTime<Tz>) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    date.format("%a %b %d %Y %I:%M%p").to_string()
+}
+
+/// # Format Long Day
+/// Formats a day without a time with the month written out, eg.
+/// "Sat Apr 04 2020"
+pub fn format_long_day(day: NaiveDate) -> String {
+    day.format("%a %b %d %Y").to_string()
+}
+
+/// # Format Relative Date
+/// Formats a date by how far away it is. Dates in the next day are given in
+/// hours and minutes ("in 3h"), dates in the next week by day and time
+/// ("tomorrow 9am", "friday 3:30pm") and dates further out in days. Past
+/// dates are given in minutes, hours or days ("2 days overdue", "3h ago").
+///
+/// ## Args
+///   - date: the date to format
+///   - now: the current time
+///   - due: whether the date is a due date, so that past dates read as
+///     overdue rather than ago
+pub fn format_relative<Tz: TimeZone>(date: DateTime<Tz>, now: DateTime<Tz>, due: bool) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    let difference = date.clone() - now.clone();
+    let days = (date.date_naive() - now.date_naive()).num_days();
+
+    if difference < Duration::zero() {
+        let suffix = if due { "overdue" } else { "ago" };
+        let elapsed = -difference;
+        return if elapsed < Duration::minutes(1) {
+            "just now".to_string()
+        } else if elapsed < Duration::hours(1) {
+            format!("{}m {}", elapsed.num_minutes(), suffix)
+        } else if elapsed < Duration::days(1) {
+            format!("{}h {}", round_hours(elapsed), suffix)
+        } else {
+            format!("{} {}", plural(elapsed.num_days(), "day"), suffix)
+        };
+    }
+
+    if difference < Duration::minutes(1) {
+        "now".to_string()
+    } else if difference < Duration::hours(1) {
+        format!("in {}m", difference.num_minutes())
+    } else if difference < Duration::hours(12) {
+        format!("in {}h", round_hours(difference))
+    } else if days == 0 {
+        format!("today {}", format_time(&date))
+    } else if days == 1 {
+        format!("tomorrow {}", format_time(&date))
+    } else if days < 7 {
+        format!(
+            "{} {}",
+            date.format("%A").to_string().to_lowercase(),
+            format_time(&date)
+        )
+    } else {
+        format!("in {}", plural(days, "day"))
+    }
+}
+
+/// # Format Relative Day
+/// Formats a day by how many days away it is: "today", "tomorrow", the
+/// name of the day in the next week ("friday") and a count of days further
+/// out ("in 9 days"). Past days are "yesterday" or a count of days
+/// ("2 days overdue", "3 days ago").
+///
+/// ## Args
+///   - day: the day to format
+///   - today: the current day
+///   - due: whether the day is a due date, so that past days read as overdue
+///     rather than ago
+pub fn format_relative_day(day: NaiveDate, today: NaiveDate, due: bool) -> String {
+    match (day - today).num_days() {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 if !due => "yesterday".to_string(),
+        2..=6 => day.format("%A").to_string().to_lowercase(),
+        days if days > 0 => format!("in {}", plural(days, "day")),
+        days => format!(
+            "{} {}",
+            plural(-days, "day"),
+            if due { "overdue" } else { "ago" }
+        ),
+    }
+}
+
+/// # Start of Day
+/// Gets the first moment of a day in a timezone. Some timezones skip
+/// midnight when daylight saving time starts so the earliest time after it
+/// is used in that case.
+pub fn start_of_day(day: NaiveDate, zone: Zone) -> DateTime<Local> {
+    zone.localize(day.and_hms_opt(0, 0, 0).unwrap())
+}
+
+/// # End of Day
+/// Gets the last moment of a day in a timezone, which is when items due all
+/// day become overdue
+pub fn end_of_day(day: NaiveDate, zone: Zone) -> DateTime<Local> {
+    start_of_day(day + Duration::days(1), zone) - Duration::nanoseconds(1)
+}
+
+/// Gets a duration to the nearest hour
+fn round_hours(duration: Duration) -> i64 {
+    (duration.num_minutes() + 30) / 60
+}
+
+/// Formats a time of day as briefly as possible, eg. "9am" or "3:30pm"
+fn format_time<Tz: TimeZone>(date: &DateTime<Tz>) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    if date.minute() == 0 {
+        date.format("%-I%P").to_string()
+    } else {
+        date.format("%-I:%M%P").to_string()
+    }
+}
+
+/// Writes a count of something, adding an s if there is more than one
+fn plural(count: i64, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// # Date Dialect
+/// How dates written as numbers are read. In the US dialect 04/05 is April
+/// 5th and in the UK dialect it is the 4th of May.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateDialect {
+    Us,
+    Uk,
+}
+
+// FromStr trait implementation so the dialect can be given in the config file
+impl FromStr for DateDialect {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DateDialect, Error> {
+        match s.to_lowercase().as_str() {
+            "us" => Ok(DateDialect::Us),
+            "uk" => Ok(DateDialect::Uk),
+            _ => Err(Error::Parse(format!(
+                "Unknown date dialect '{}'. Expected us or uk",
+                s
+            ))),
+        }
+    }
+}
+
+// Display trait implementation that writes the dialect name back out
+impl fmt::Display for DateDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// Date and time formats that are read exactly, before trying English
+const EXPLICIT_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// # Parse Date
+/// Parses a date typed by the user. ISO 8601 dates with a timezone
+/// ("2020-04-05T17:00:00+01:00") and ISO dates with a time
+/// ("2020-04-05 17:00") are read exactly. Anything else is read as English
+/// with the chrono_english crate, eg. "tomorrow 5pm", "friday", "2d",
+/// "2020-04-05" or "04/05".
+///
+/// Times are read on the clock of the given zone. Offsets of whole days,
+/// weeks, months or years keep the time on the clock, so a 9am deadline
+/// moved on by a day is still at 9am after the clocks change for daylight
+/// saving time. Offsets of hours, minutes and seconds are exact.
+///
+/// ## Args
+///   - text: the date as typed
+///   - base: the time relative dates like "2d" are counted from
+///   - dialect: how dates written as numbers are read
+///   - zone: the timezone the date is typed in
+///
+/// ## Rets
+///   - Result with the date or an error suggesting what to type instead
+pub fn parse_date(
+    text: &str,
+    base: DateTime<Local>,
+    dialect: DateDialect,
+    zone: Zone,
+) -> Result<DateTime<Local>, Error> {
+    let text = text.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Ok(date.with_timezone(&Local));
+    }
+    for format in EXPLICIT_FORMATS {
+        if let Ok(date) = NaiveDateTime::parse_from_str(text, format) {
+            return Ok(zone.localize(date));
+        }
+    }
+    if let Ok(offset) = parse_duration(text) {
+        return Ok(shift(base, offset, zone));
+    }
+
+    // chrono_english gives the date with the offset the base has, which is
+    // wrong if the clocks change in between. Reading the time on the clock
+    // back in the zone puts it right
+    let dialect = match dialect {
+        DateDialect::Us => Dialect::Us,
+        DateDialect::Uk => Dialect::Uk,
+    };
+    parse_date_string(text, zone.at(base), dialect)
+        .map(|date| zone.localize(date.naive_local()))
+        .map_err(|_| {
+            Error::Parse(format!(
+                "Unable to parse date '{}'. Try something like 'tomorrow 5pm', 'friday', '2d', \
+                 '2020-04-05' or '2020-04-05 17:00'",
+                text
+            ))
+        })
+}
+
+/// # Shift
+/// Moves a date on by an offset. Whole days and months are counted on the
+/// clock of the zone so the time of day stays the same, while shorter
+/// offsets are an exact amount of time.
+fn shift(base: DateTime<Local>, offset: Interval, zone: Zone) -> DateTime<Local> {
+    let clock = zone.at(base).naive_local();
+    let moved = match offset {
+        Interval::Seconds(seconds) => return base + Duration::seconds(i64::from(seconds)),
+        Interval::Days(days) => clock.checked_add_signed(Duration::days(i64::from(days))),
+        Interval::Months(months) if months < 0 => {
+            clock.checked_sub_months(Months::new(months.unsigned_abs()))
+        }
+        Interval::Months(months) => clock.checked_add_months(Months::new(months as u32)),
+    };
+    moved.map_or(base, |moved| zone.localize(moved))
+}
+
+/// # Parse Due Date
+/// Parses a due date typed by the user in the same way as `parse_date`. A
+/// date that doesn't name a time, eg. "friday", "tomorrow", "2d" or
+/// "2020-04-05", is due all day and is given as the end of that day.
+///
+/// ## Args
+///   - text: the date as typed
+///   - base: the time relative dates like "2d" are counted from
+///   - dialect: how dates written as numbers are read
+///   - zone: the timezone the date is typed in
+///
+/// ## Rets
+///   - Result with the due date and whether it is all day, or an error
+///     suggesting what to type instead
+pub fn parse_due(
+    text: &str,
+    base: DateTime<Local>,
+    dialect: DateDialect,
+    zone: Zone,
+) -> Result<(DateTime<Local>, bool), Error> {
+    let due = parse_date(text, base, dialect, zone)?;
+    if names_time(text) {
+        Ok((due, false))
+    } else {
+        Ok((end_of_day(zone.at(due).date_naive(), zone), true))
+    }
+}
+
+/// # Names Time
+/// Whether a typed date gives a time of day or an offset smaller than a day,
+/// eg. "5pm", "17:00", "noon", "now", "3hr" or "30m", rather than just a day
+pub fn names_time(text: &str) -> bool {
+    lazy_static! {
+        static ref TIME_RE: Regex =
+            Regex::new(r"(?i)\d:\d|\d\s*[ap]\.?m\b|\b(now|noon|midnight)\b").unwrap();
+    }
+    match parse_duration(text.trim()) {
+        Ok(Interval::Seconds(_)) => true,
+        Ok(_) => false,
+        Err(_) => TIME_RE.is_match(text),
+    }
+}
+
+/// # Date Display
+/// The style each date column of a table is shown in. Set in the config
+/// file with `dates.<column> = <style>`, eg. `dates.due = relative`.
+///
+/// ## Data Members
+///   - due: style of due dates
+///   - created: style of the dates items were created
+///   - completed: style of the dates items were completed
+///   - wait: style of the dates items are hidden until
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DateDisplay {
+    pub due: DateStyle,
+    pub created: DateStyle,
+    pub completed: DateStyle,
+    pub wait: DateStyle,
+}
+
+impl Default for DateDisplay {
+    fn default() -> DateDisplay {
+        DateDisplay {
+            due: DateStyle::Absolute,
+            created: DateStyle::Absolute,
+            completed: DateStyle::Absolute,
+            wait: DateStyle::Absolute,
+        }
+    }
+}
+
+impl DateDisplay {
+    /// Shows every column in the same style
+    pub fn all(style: DateStyle) -> DateDisplay {
+        DateDisplay {
+            due: style,
+            created: style,
+            completed: style,
+            wait: style,
+        }
+    }
+}
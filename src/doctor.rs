@@ -0,0 +1,302 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::migration;
+use crate::priority::PriorityScheme;
+use crate::todo_item::{self, TodoItem};
+use crate::todo_list::{self, TodoList};
+
+/// # Problem
+/// A single thing that is wrong with a line of the data file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    Unparseable,
+    InvalidPriority(String),
+    PriorityOutOfRange(i64, PriorityScheme),
+    InvalidDate(String),
+    InvalidAttribute(String),
+    MissingId,
+    DuplicateId(u32),
+}
+
+// Display trait implementation so problems can be reported to the user
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Unparseable => write!(f, "line is not a todo item"),
+            Problem::InvalidPriority(priority) => {
+                write!(f, "priority '{}' is not a number", priority)
+            }
+            Problem::PriorityOutOfRange(priority, scheme) => write!(
+                f,
+                "priority {} is outside of 0-{} for {} priorities",
+                priority,
+                scheme.lowest(),
+                scheme
+            ),
+            Problem::InvalidDate(due) => write!(f, "due date '{}' is invalid", due),
+            Problem::InvalidAttribute(attribute) => {
+                write!(f, "attribute '{}' is invalid", attribute)
+            }
+            Problem::MissingId => write!(f, "item has no id"),
+            Problem::DuplicateId(id) => write!(f, "id {} is used by an earlier item", id),
+        }
+    }
+}
+
+/// # Repair
+/// What will be done to a line with problems when it is repaired.
+///
+/// ## Variants
+///   - Replace: the line is replaced by this item
+///   - Quarantine: the line is moved out of the data file into the
+///     quarantine file next to it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Repair {
+    Replace(TodoItem),
+    Quarantine,
+}
+
+// Display trait implementation so repairs can be described to the user
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::Replace(item) => write!(f, "replace with: {}", item),
+            Repair::Quarantine => write!(f, "move to the quarantine file"),
+        }
+    }
+}
+
+/// # Diagnosis
+/// Everything wrong with one line of the data file and how to fix it.
+///
+/// ## Data Members
+///   - line: line number in the data file, starting at 1
+///   - text: the line as it is in the data file
+///   - problems: every problem found on the line
+///   - repair: what repairing the line will do
+#[derive(Clone, Debug)]
+pub struct Diagnosis {
+    pub line: usize,
+    pub text: String,
+    pub problems: Vec<Problem>,
+    pub repair: Repair,
+}
+
+/// # Repair Summary
+/// The result of repairing a data file.
+///
+/// ## Data Members
+///   - backup: copy of the data file from before the repair
+///   - quarantine: the file quarantined lines were moved to, if any were
+///   - replaced: number of lines that were replaced
+///   - quarantined: number of lines that were quarantined
+#[derive(Clone, Debug)]
+pub struct RepairSummary {
+    pub backup: PathBuf,
+    pub quarantine: Option<PathBuf>,
+    pub replaced: usize,
+    pub quarantined: usize,
+}
+
+/// # Examine
+/// Scans the data file for problems. The file is opened as a todo list first
+/// so that older formats are upgraded before they are checked.
+///
+/// ## Args
+///   - path: location of the data file
+///
+/// ## Rets
+///   - Result with a diagnosis for every line with problems, in file order
+pub fn examine(path: &Path) -> Result<Vec<Diagnosis>> {
+    let list = TodoList::open(path)?;
+    let scheme = list.scheme();
+    let lines = read_lines(path)?;
+
+    // Diagnose each line on its own first. The first line is the version
+    // header so it is skipped
+    let mut diagnoses = Vec::new();
+    let mut items = Vec::new();
+    for (number, line) in lines.iter().enumerate().skip(1) {
+        let (problems, repair) = diagnose_line(line, scheme);
+        if let Repair::Replace(item) = &repair {
+            items.push((diagnoses.len(), item.id));
+        }
+        diagnoses.push(Diagnosis {
+            line: number + 1,
+            text: line.clone(),
+            problems,
+            repair,
+        });
+    }
+
+    // Ids have to be unique across the whole file. The first item to use an
+    // id keeps it and every other item is given a fresh one. Fresh ids come
+    // after every id in the archive too, as well as those of repaired lines
+    // that the list couldn't parse
+    let newest = items.iter().map(|(_, id)| *id).max().unwrap_or(0);
+    let mut next_id = list.next_id().max(newest + 1);
+    let mut seen = HashSet::new();
+    for (index, id) in items {
+        let problem = if id == 0 {
+            Problem::MissingId
+        } else if !seen.insert(id) {
+            Problem::DuplicateId(id)
+        } else {
+            continue;
+        };
+
+        let diagnosis = &mut diagnoses[index];
+        diagnosis.problems.push(problem);
+        if let Repair::Replace(item) = &mut diagnosis.repair {
+            item.id = next_id;
+            next_id += 1;
+        }
+    }
+
+    Ok(diagnoses
+        .into_iter()
+        .filter(|diagnosis| !diagnosis.problems.is_empty())
+        .collect())
+}
+
+/// # Repair
+/// Applies the repairs of the given diagnoses to the data file. A backup of
+/// the file is taken first and quarantined lines are appended to a
+/// `.quarantine` file next to the data file.
+///
+/// ## Args
+///   - path: location of the data file
+///   - diagnoses: the diagnoses to repair, as returned by examine
+///
+/// ## Rets
+///   - Result with a summary of what was changed
+pub fn repair(path: &Path, diagnoses: &[Diagnosis]) -> Result<RepairSummary> {
+    let backup = migration::backup(path, "doctor")?;
+    let repairs: HashMap<usize, &Repair> = diagnoses
+        .iter()
+        .map(|diagnosis| (diagnosis.line, &diagnosis.repair))
+        .collect();
+
+    let mut summary = RepairSummary {
+        backup,
+        quarantine: None,
+        replaced: 0,
+        quarantined: 0,
+    };
+
+    let mut kept = Vec::new();
+    let mut quarantined = Vec::new();
+    for (number, line) in read_lines(path)?.into_iter().enumerate() {
+        match repairs.get(&(number + 1)) {
+            Some(Repair::Replace(item)) => {
+                kept.push(item.to_string());
+                summary.replaced += 1;
+            }
+            Some(Repair::Quarantine) => {
+                quarantined.push(line);
+                summary.quarantined += 1;
+            }
+            None => kept.push(line),
+        }
+    }
+
+    // Quarantine the lines before they are removed from the data file so
+    // that nothing is lost if writing fails part way
+    if !quarantined.is_empty() {
+        let mut quarantine_path = path.to_path_buf().into_os_string();
+        quarantine_path.push(".quarantine");
+        let quarantine_path = PathBuf::from(quarantine_path);
+
+        let mut quarantine_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&quarantine_path)?;
+        for line in &quarantined {
+            writeln!(quarantine_file, "{}", line)?;
+        }
+        summary.quarantine = Some(quarantine_path);
+    }
+
+    todo_list::write_lines(path, &kept)?;
+    Ok(summary)
+}
+
+/// # Diagnose Line
+/// Finds every problem with a single line of the data file. The line is
+/// broken into its parts leniently so that each part can be checked, and
+/// fixed, on its own.
+///
+/// ## Args
+///   - line: the line from the data file
+///   - scheme: how the priorities of the list are written
+///
+/// ## Rets
+///   - The problems with the line and how to repair it. If there are no
+///     problems the repair is the item the line already describes
+fn diagnose_line(line: &str, scheme: PriorityScheme) -> (Vec<Problem>, Repair) {
+    lazy_static! {
+        static ref LOOSE_RE: Regex =
+            Regex::new(r"^\[([^\]]*)\]\{([^}]*)\} ([^\t]*)((?:\t[^\t]*)*)$").unwrap();
+    }
+
+    let cap = match LOOSE_RE.captures(line) {
+        Some(cap) => cap,
+        None => return (vec![Problem::Unparseable], Repair::Quarantine),
+    };
+
+    let mut problems = Vec::new();
+    let mut item = TodoItem {
+        item: cap[3].to_string(),
+        ..TodoItem::default()
+    };
+
+    // A bad priority is clamped into range, or dropped if it isn't a number
+    let priority = &cap[1];
+    if !priority.is_empty() {
+        let lowest = i64::from(scheme.lowest());
+        match priority.trim().parse::<i64>() {
+            Ok(value) if (0..=lowest).contains(&value) => item.priority = Some(value as i8),
+            Ok(value) => {
+                problems.push(Problem::PriorityOutOfRange(value, scheme));
+                item.priority = Some(value.clamp(0, lowest) as i8);
+            }
+            Err(_) => problems.push(Problem::InvalidPriority(priority.to_string())),
+        }
+    }
+
+    // Bad attributes are dropped
+    for attribute in cap[4].split('\t').skip(1) {
+        if item.set_attribute(attribute).is_err() {
+            problems.push(Problem::InvalidAttribute(attribute.to_string()));
+        }
+    }
+
+    // A bad due date is dropped. It is read after the attributes since the
+    // day of an item due all day is in the timezone they give
+    let due = &cap[2];
+    if !due.is_empty() {
+        match todo_item::parse_due(due, item.zone()) {
+            Ok((due, all_day)) => {
+                item.due = Some(due);
+                item.all_day = all_day;
+            }
+            Err(_) => problems.push(Problem::InvalidDate(due.to_string())),
+        }
+    }
+
+    (problems, Repair::Replace(item))
+}
+
+/// # Read Lines
+/// Reads every line of a file
+fn read_lines(path: &Path) -> Result<Vec<String>> {
+    Ok(io::BufReader::new(File::open(path)?)
+        .lines()
+        .collect::<io::Result<Vec<String>>>()?)
+}
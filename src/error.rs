@@ -0,0 +1,51 @@
+use std::fmt;
+use std::io;
+
+/// # Error
+/// Every way that reading or writing a todo list can fail.
+///
+/// ## Variants
+///   - Io: the data file couldn't be read or written
+///   - NoConfigDir: the platform config directory couldn't be found
+///   - Parse: a line or value couldn't be understood
+///   - UnsupportedVersion: the data file was written by a newer version
+///   - NoSuchItem: there is no item with the given id
+///   - NoSuchTemplate: there is no template with the given name
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    NoConfigDir,
+    Parse(String),
+    UnsupportedVersion { found: u32, supported: u32 },
+    NoSuchItem(u32),
+    NoSuchTemplate(String),
+}
+
+/// Result type used throughout the library
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::NoConfigDir => write!(f, "Could not find config directory"),
+            Error::Parse(message) => write!(f, "{}", message),
+            Error::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Data file is format version {} but only versions up to {} are supported. \
+                 Please upgrade todo.",
+                found, supported
+            ),
+            Error::NoSuchItem(id) => write!(f, "There is no todo item with id {}", id),
+            Error::NoSuchTemplate(name) => write!(f, "There is no template named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
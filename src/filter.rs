@@ -0,0 +1,83 @@
+use chrono::{DateTime, Local};
+
+use crate::todo_item::TodoItem;
+
+/// # Filter
+/// Describes which todo items to keep. Every field that is set must match for
+/// an item to be kept, so the default filter keeps everything.
+///
+/// ## Data Members
+///   - text: case insensitive text the item must contain
+///   - min_priority: lowest priority number to keep (inclusive)
+///   - max_priority: highest priority number to keep (inclusive)
+///   - due_after: keep items due after this date
+///   - due_before: keep items due before this date
+///   - projects: projects the item must be in, ignoring case
+///   - contexts: contexts the item must have, ignoring case
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub text: Option<String>,
+    pub min_priority: Option<i8>,
+    pub max_priority: Option<i8>,
+    pub due_after: Option<DateTime<Local>>,
+    pub due_before: Option<DateTime<Local>>,
+    pub projects: Vec<String>,
+    pub contexts: Vec<String>,
+}
+
+impl Filter {
+    /// # Matches
+    /// Checks if a todo item passes every part of the filter. Items without
+    /// a priority or due date never match a filter on that field.
+    pub fn matches(&self, item: &TodoItem) -> bool {
+        if let Some(text) = &self.text {
+            if !item.item.to_lowercase().contains(&text.to_lowercase()) {
+                return false;
+            }
+        }
+
+        if let Some(min) = self.min_priority {
+            if item.priority.is_none_or(|priority| priority < min) {
+                return false;
+            }
+        }
+
+        if let Some(max) = self.max_priority {
+            if item.priority.is_none_or(|priority| priority > max) {
+                return false;
+            }
+        }
+
+        if let Some(after) = self.due_after {
+            if item.due.is_none_or(|due| due <= after) {
+                return false;
+            }
+        }
+
+        if let Some(before) = self.due_before {
+            if item.due.is_none_or(|due| due >= before) {
+                return false;
+            }
+        }
+
+        let projects = item.projects();
+        if !self.projects.iter().all(|wanted| {
+            projects
+                .iter()
+                .any(|project| project.eq_ignore_ascii_case(wanted))
+        }) {
+            return false;
+        }
+
+        let contexts = item.contexts();
+        if !self.contexts.iter().all(|wanted| {
+            contexts
+                .iter()
+                .any(|context| context.eq_ignore_ascii_case(wanted))
+        }) {
+            return false;
+        }
+
+        true
+    }
+}
@@ -0,0 +1,67 @@
+//! # To Done
+//! Library for reading and writing plain text todo lists. This is the same
+//! code that powers the `todo` command line application, so anything built on
+//! it shares the data file and its format.
+//!
+//! Todo items are stored one per line in the form
+//! `[Optional Priority]{Optional Date} TEXT` followed by tab separated
+//! attributes, under a header line giving the format version and how the
+//! list writes its priorities. A `TodoList`
+//! reads these from a data file into `TodoItem`s which can be sorted,
+//! filtered, added to and saved back. Data files written by older versions
+//! are upgraded automatically when they are opened.
+//!
+//! ```no_run
+//! use chrono::Local;
+//! use to_done::{Config, SortSpec, TodoItem, TodoList};
+//!
+//! let mut list = TodoList::open_default().unwrap();
+//! list.add(TodoItem {
+//!     priority: Some(2),
+//!     item: "Take out the trash".to_string(),
+//!     ..TodoItem::default()
+//! });
+//! let spec: SortSpec = "due,-priority".parse().unwrap();
+//! list.sort(&spec, &Config::default().urgency, Local::now());
+//! list.save().unwrap();
+//! ```
+#[macro_use]
+extern crate lazy_static;
+
+pub mod agenda;
+pub mod calendar;
+pub mod config;
+pub mod dates;
+pub mod doctor;
+pub mod error;
+pub mod filter;
+pub mod link;
+pub mod migration;
+pub mod priority;
+pub mod quick_add;
+pub mod render;
+pub mod selector;
+pub mod sort;
+pub mod template;
+pub mod time_tracking;
+pub mod timesheet;
+pub mod todo_item;
+pub mod todo_list;
+pub mod urgency;
+pub mod workload;
+pub mod zone;
+
+pub use config::Config;
+pub use dates::{DateDialect, DateStyle, DueBand};
+pub use error::{Error, Result};
+pub use filter::Filter;
+pub use priority::PriorityScheme;
+pub use quick_add::QuickAdd;
+pub use render::{Format, Renderer};
+pub use selector::Selector;
+pub use sort::{SortField, SortKey, SortSpec};
+pub use template::Template;
+pub use time_tracking::TimeInterval;
+pub use todo_item::TodoItem;
+pub use todo_list::TodoList;
+pub use zone::Zone;
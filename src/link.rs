@@ -0,0 +1,110 @@
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// # Is URL
+/// Whether a link is a URL like `https://example.com` or
+/// `mailto:me@example.com` rather than a path to a local file
+pub fn is_url(link: &str) -> bool {
+    lazy_static! {
+        static ref URL_RE: Regex =
+            Regex::new(r"^(?:[A-Za-z][A-Za-z0-9+.-]*://|mailto:)\S").unwrap();
+    }
+    URL_RE.is_match(link)
+}
+
+/// # Resolve Link
+/// Checks a link given on the command line and gets it in the form it is
+/// stored in. URLs are kept as they are. Paths are made absolute, with a
+/// leading `~` standing for the home directory, so that they can be opened
+/// from anywhere, and have to point at a file or directory that exists.
+///
+/// ## Args
+///   - link: the link as it was typed
+///   - base: the directory relative paths are relative to
+///
+/// ## Rets
+///   - Result with the link to store or an error saying what is wrong
+///     with it
+pub fn resolve(link: &str, base: &Path) -> Result<String> {
+    let link = link.trim();
+    if link.is_empty() {
+        return Err(Error::Parse(String::from("Links can't be empty")));
+    }
+    // Tabs separate the attributes of the data file
+    if link.contains('\t') {
+        return Err(Error::Parse(format!(
+            "Links can't contain tabs: '{}'",
+            link
+        )));
+    }
+    if is_url(link) {
+        return Ok(link.to_string());
+    }
+
+    let path = match link.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(link),
+        },
+        _ => PathBuf::from(link),
+    };
+    let path = fs::canonicalize(base.join(path))
+        .map_err(|e| Error::Parse(format!("Can't link to '{}'. {}", link, e)))?;
+    path.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| Error::Parse(format!("Can't link to '{}'. The path isn't UTF-8", link)))
+}
+
+/// # URI
+/// Gets the URI a link points to, turning paths into `file://` URIs. Bytes
+/// of the path that aren't allowed in a URI are percent encoded.
+pub fn uri(link: &str) -> String {
+    if is_url(link) {
+        return link.to_string();
+    }
+
+    let mut uri = String::from("file://");
+    for byte in link.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// # Label
+/// Gets a short name for a link to show in place of all of it: the host of
+/// a URL, the address of a mailto link or the name of a file
+pub fn label(link: &str) -> String {
+    if let Some(address) = link.strip_prefix("mailto:") {
+        return address.to_string();
+    }
+    if let Some(split) = link.find("://") {
+        let rest = &link[split + 3..];
+        let host = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+        // Leave out any user name before the host
+        let host = host.rsplit('@').next().unwrap_or(host);
+        return host.trim_start_matches("www.").to_string();
+    }
+    Path::new(link)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| link.to_string())
+}
+
+/// # Hyperlink
+/// Wraps text in an OSC 8 escape so that terminals that support it open the
+/// link when the text is clicked. Terminals that don't just show the text.
+///
+/// ## Args
+///   - link: the link to open
+///   - text: the text to show
+pub fn hyperlink(link: &str, text: &str) -> String {
+    format!("\u{1b}]8;;{}\u{1b}\\{}\u{1b}]8;;\u{1b}\\", uri(link), text)
+}
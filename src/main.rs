@@ -1,20 +1,36 @@
-extern crate chrono_english;
-#[macro_use]
-extern crate lazy_static;
+extern crate clap;
 #[macro_use]
 extern crate prettytable;
-extern crate clap;
+extern crate to_done;
 
-use chrono::Local;
-use chrono_english::{parse_date_string, Dialect};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use chrono_tz::Tz;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use prettytable::{Cell, Row, Table};
-use std::cmp::Ordering;
-use termion::{color, style};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::process::{Command, Stdio};
+use termion::style;
+use to_done::agenda;
+use to_done::calendar::{Calendar, Span};
+use to_done::dates::{self, DateDisplay};
+use to_done::doctor::{self, Diagnosis};
+use to_done::link;
+use to_done::migration;
+use to_done::render::{self, ColorChoice};
+use to_done::time_tracking::format_duration;
+use to_done::timesheet::{self, Timesheet};
+use to_done::urgency::Scorer;
+use to_done::workload::{self, Workload};
+use to_done::{
+    Config, DateStyle, DueBand, Filter, Format, PriorityScheme, QuickAdd, Selector, SortSpec,
+    Template, TodoItem, TodoList, Zone,
+};
 
 #[macro_use]
 mod log;
-mod todo_item;
 
 // Constant string for the main author
 const ME: &str = "Alex Day <alex@alexday.me>";
@@ -36,6 +52,9 @@ fn main() {
         // todo new "Task with priority" -p 4
         // todo new "Task with date" -d 3h
         // todo new "Task with both" -p 3 -d Apr 4
+        // todo new -q "Call dentist tomorrow 3pm !2 +health @phone" --dry-run
+        // todo new "Review the report" --link ~/reports/q3.pdf
+        // todo new - < items.txt
         .subcommand(
             SubCommand::with_name("new")
                 .version("0.0.1")
@@ -43,8 +62,9 @@ fn main() {
                 // Required argument that contains the todo item text
                 .arg(
                     Arg::with_name("item")
-                        .help("Todo item to add")
-                        .required(true),
+                        .help("Todo item to add, or - to add one item per line of stdin the way --quick reads them")
+                        .required(true)
+                        .validator(is_item_text),
                 )
                 // Optional argument for the priority
                 .arg(
@@ -53,8 +73,7 @@ fn main() {
                         .long("priority")
                         .required(false)
                         .takes_value(true)
-                        .validator(is_priority)
-                        .help("Priority of the todo item [OPTIONAL]"),
+                        .help("Priority of the todo item in the scheme of the list, eg. 2, B or high [OPTIONAL]"),
                 )
                 // Optional argument for the due date
                 .arg(
@@ -64,6 +83,75 @@ fn main() {
                         .required(false)
                         .takes_value(true)
                         .help("Due date for the todo item [OPTIONAL]"),
+                )
+                .arg(allow_past_arg())
+                .arg(tz_arg("Read dates in this timezone, eg. America/New_York or UTC. Defaults to timezone from the config [OPTIONAL]"))
+                // Optional argument for when the item should start showing
+                .arg(
+                    Arg::with_name("wait")
+                        .short("w")
+                        .long("wait")
+                        .visible_alias("start")
+                        .required(false)
+                        .takes_value(true)
+                        .help("Hide the todo item until this date [OPTIONAL]"),
+                )
+                // Optional argument for the effort estimate
+                .arg(
+                    Arg::with_name("estimate")
+                        .short("e")
+                        .long("estimate")
+                        .required(false)
+                        .takes_value(true)
+                        .validator(is_estimate)
+                        .help("How long the todo item will take, eg. 2h or 1h30m [OPTIONAL]"),
+                )
+                // Optional argument for the items this one depends on
+                .arg(
+                    Arg::with_name("depends")
+                        .long("depends")
+                        .required(false)
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(is_id)
+                        .help("Id of a todo item that has to be done first. Can be repeated [OPTIONAL]"),
+                )
+                // Optional argument for the links of the item
+                .arg(link_arg("URL or path of a file that goes with the todo item. Can be repeated [OPTIONAL]"))
+                // Optional flag to read the priority and due date from the
+                // todo item text
+                .arg(
+                    Arg::with_name("quick")
+                        .short("q")
+                        .long("quick")
+                        .help("Read a priority like !2, a due date like 'tomorrow 3pm' and URLs to link to from the todo item text. --priority and --due still win"),
+                )
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("Show the todo item that would be added without saving it"),
+                ),
+        )
+        // Add an import-lines subcommand that adds one item per line of a
+        // file. The use is as follows:
+        // todo import-lines items.txt
+        // todo import-lines items.txt --dry-run
+        .subcommand(
+            SubCommand::with_name("import-lines")
+                .version("0.0.1")
+                .author(ME)
+                .about("Add a todo item for each line of a file, reading priorities and due dates the way new --quick does")
+                .arg(
+                    Arg::with_name("file")
+                        .help("File with one todo item per line. Blank lines and lines starting with # are skipped")
+                        .required(true),
+                )
+                .arg(tz_arg("Read dates in this timezone, eg. America/New_York or UTC. Defaults to timezone from the config [OPTIONAL]"))
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("Show the todo items that would be added without saving them"),
                 ),
         )
         // Add a view subcommand that displays all of the current todo items
@@ -73,15 +161,394 @@ fn main() {
             SubCommand::with_name("view")
                 .version("0.0.1")
                 .author(ME)
+                .args(&display_args())
+                .arg(
+                    Arg::with_name("all")
+                        .short("a")
+                        .long("all")
+                        .help("Show todo items that are done or waiting as well"),
+                )
+                .arg(
+                    Arg::with_name("waiting")
+                        .long("waiting")
+                        .conflicts_with("archived")
+                        .help("Only show the todo items that are waiting to start"),
+                )
+                .arg(
+                    Arg::with_name("archived")
+                        .long("archived")
+                        .help("Show the archived todo items instead"),
+                )
+                .arg(
+                    Arg::with_name("search")
+                        .long("search")
+                        .takes_value(true)
+                        .help("Only show todo items containing this text, ignoring case [OPTIONAL]"),
+                ),
+        )
+        // Add agenda subcommands that group the items that aren't done by
+        // how soon they are due. The use is as follows:
+        // todo today
+        // todo upcoming --days 7
+        // todo overdue
+        .subcommand(
+            SubCommand::with_name("today")
+                .version("0.0.1")
+                .author(ME)
+                .about("Show the todo items that are overdue or due today")
+                .args(&display_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("upcoming")
+                .version("0.0.1")
+                .author(ME)
+                .about("Show the todo items that aren't done grouped by when they are due")
+                .args(&display_args())
+                .arg(
+                    Arg::with_name("days")
+                        .long("days")
+                        .takes_value(true)
+                        .validator(is_count)
+                        .help("Only show items due in this many days, leaving out those without a due date [OPTIONAL]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("overdue")
+                .version("0.0.1")
+                .author(ME)
+                .about("Show the todo items that are overdue")
+                .args(&display_args()),
+        )
+        // Add a calendar subcommand that lays out the items by the day they
+        // are due. The use is as follows:
+        // todo cal
+        // todo cal week --offset 1
+        // todo cal month --date "Dec 1" --titles
+        .subcommand(
+            SubCommand::with_name("cal")
+                .version("0.0.1")
+                .author(ME)
+                .about("Show a calendar of when todo items are due")
+                .arg(
+                    Arg::with_name("span")
+                        .help("Whether to show a month or a week")
+                        .possible_values(&["month", "week"])
+                        .default_value("month"),
+                )
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .takes_value(true)
+                        .help("Show the month or week containing this date instead of today [OPTIONAL]"),
+                )
+                .arg(
+                    Arg::with_name("offset")
+                        .long("offset")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .validator(is_offset)
+                        .help("Move forward this many months or weeks, or back if negative [OPTIONAL]"),
+                )
+                .arg(
+                    Arg::with_name("titles")
+                        .long("titles")
+                        .help("Show the items due each day instead of how many there are"),
+                )
+                .arg(color_arg()),
+        )
+        // Add an archive subcommand that moves done items to the archive
+        // file. The use is as follows:
+        // todo archive
+        // todo archive --days 14
+        .subcommand(
+            SubCommand::with_name("archive")
+                .version("0.0.1")
+                .author(ME)
+                .about("Move todo items that are done to the archive file")
+                .arg(
+                    Arg::with_name("days")
+                        .long("days")
+                        .takes_value(true)
+                        .validator(is_count)
+                        .help("Only archive items completed more than this many days ago [OPTIONAL]"),
+                ),
+        )
+        // Add a done subcommand that marks todo items as done. The use is
+        // as follows:
+        // todo done 3
+        // todo done pri:9 +cleanup
+        .subcommand(
+            SubCommand::with_name("done")
+                .version("0.0.1")
+                .author(ME)
+                .about("Mark todo items as done")
+                .args(&selector_args()),
+        )
+        // Add an edit subcommand that changes the fields of todo items. The
+        // use is as follows:
+        // todo edit 3 --due friday
+        // todo edit due:<today --priority 0
+        // todo edit 3 --link https://example.com/issues/12
+        .subcommand(
+            SubCommand::with_name("edit")
+                .version("0.0.1")
+                .author(ME)
+                .about("Change the priority, due date, wait date, estimate or links of todo items")
+                .args(&selector_args())
+                .arg(
+                    Arg::with_name("priority")
+                        .short("p")
+                        .long("priority")
+                        .takes_value(true)
+                        .help("New priority in the scheme of the list, or 'none' to remove it [OPTIONAL]"),
+                )
+                .arg(
+                    Arg::with_name("due")
+                        .short("d")
+                        .long("due")
+                        .takes_value(true)
+                        .help("New due date, or 'none' to remove it [OPTIONAL]"),
+                )
+                .arg(allow_past_arg())
+                .arg(
+                    Arg::with_name("wait")
+                        .short("w")
+                        .long("wait")
+                        .visible_alias("start")
+                        .takes_value(true)
+                        .help("New date to hide the items until, or 'none' to show them [OPTIONAL]"),
+                )
+                .arg(
+                    Arg::with_name("estimate")
+                        .short("e")
+                        .long("estimate")
+                        .takes_value(true)
+                        .validator(|val| or_none(val, is_estimate))
+                        .help("New estimate, or 'none' to remove it [OPTIONAL]"),
+                )
+                .arg(link_arg("URL or path of a file to link to, or 'none' to remove every link. Can be repeated [OPTIONAL]")),
+        )
+        // Add snooze and reschedule subcommands that move due dates. Snoozing
+        // moves a due date on from where it is, rescheduling sets it from
+        // now. The use is as follows:
+        // todo snooze 3 2d
+        // todo reschedule overdue "next monday"
+        // todo reschedule +garden friday
+        .subcommand(
+            SubCommand::with_name("snooze")
+                .version("0.0.1")
+                .author(ME)
+                .about("Push the due date of todo items back")
+                .args(&selector_args())
+                .arg(
+                    Arg::with_name("when")
+                        .help("How long to push the due date back by, eg. 2d or '3 hours'. Items without a due date are pushed back from now")
+                        .required(true),
+                )
+                .arg(allow_past_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("reschedule")
+                .version("0.0.1")
+                .author(ME)
+                .about("Give todo items a new due date")
+                .args(&selector_args())
+                .arg(
+                    Arg::with_name("when")
+                        .help("The new due date, eg. 'next monday' or friday")
+                        .required(true),
+                )
+                .arg(allow_past_arg()),
+        )
+        // Add a doctor subcommand that checks the data file for problems and
+        // optionally repairs them. The use is as follows:
+        // todo doctor
+        // todo doctor --fix
+        // todo doctor --interactive
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .version("0.0.1")
+                .author(ME)
+                .about("Check the data file for problems and repair them")
+                .arg(
+                    Arg::with_name("fix")
+                        .long("fix")
+                        .help("Repair every problem found"),
+                )
+                .arg(
+                    Arg::with_name("interactive")
+                        .short("i")
+                        .long("interactive")
+                        .conflicts_with("fix")
+                        .help("Ask before repairing each line with problems"),
+                ),
+        )
+        // Add start and stop subcommands that track time spent working on
+        // an item. Only one item can be worked on at a time. The use is as
+        // follows:
+        // todo start 3
+        // todo stop
+        .subcommand(
+            SubCommand::with_name("start")
+                .version("0.0.1")
+                .author(ME)
+                .about("Start tracking time against a todo item")
+                .arg(
+                    Arg::with_name("id")
+                        .help("Id of the todo item to work on")
+                        .required(true)
+                        .validator(is_id),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stop")
+                .version("0.0.1")
+                .author(ME)
+                .about("Stop tracking time against the running todo item"),
+        )
+        // Add an open subcommand that opens the links of an item with the
+        // opener from the config. The use is as follows:
+        // todo open 3
+        .subcommand(
+            SubCommand::with_name("open")
+                .version("0.0.1")
+                .author(ME)
+                .about("Open the links of a todo item with the opener command from the config")
+                .arg(
+                    Arg::with_name("id")
+                        .help("Id of the todo item to open the links of")
+                        .required(true)
+                        .validator(is_id),
+                ),
+        )
+        // Add a timesheet subcommand that reports the time tracked per item,
+        // project and day. The range defaults to the last week. The use is
+        // as follows:
+        // todo timesheet
+        // todo timesheet --from "last monday" --to today --csv
+        .subcommand(
+            SubCommand::with_name("timesheet")
+                .version("0.0.1")
+                .author(ME)
+                .about("Report the time tracked against todo items")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .help("First day to report on. Defaults to 6 days ago [OPTIONAL]"),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .takes_value(true)
+                        .help("Last day to report on. Defaults to today [OPTIONAL]"),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .help("Print every entry as CSV instead of summary tables"),
+                )
+                .arg(tz_arg("Read and show days in this timezone, eg. America/New_York or UTC. Defaults to timezone from the config [OPTIONAL]")),
+        )
+        // Add a workload subcommand that forecasts the estimated work on
+        // items per day and week up to their due dates and flags days with
+        // more work than fits. The use is as follows:
+        // todo workload
+        // todo workload --days 30 --capacity 6h
+        .subcommand(
+            SubCommand::with_name("workload")
+                .version("0.0.1")
+                .author(ME)
+                .about("Forecast the estimated work per day up to due dates")
+                .arg(
+                    Arg::with_name("days")
+                        .long("days")
+                        .takes_value(true)
+                        .default_value("14")
+                        .validator(is_forecast_days)
+                        .help("How many days to forecast"),
+                )
                 .arg(
-                    Arg::with_name("sort")
-                        .help("Sorting the todo items.")
-                        .long_help("Used to sort the todo items. 'd' and 'da' sort by due date ascending. 'dd' sorts by due date descending. 'p' and 'pa' sort by priority ascending. 'pd' sorts by due date descending.")
+                    Arg::with_name("capacity")
+                        .long("capacity")
                         .takes_value(true)
-                        .short("s")
-                        .long("sort")
-                        .possible_values(&["d", "da", "dd", "p", "pd", "pa"])
-                        .hide_possible_values(true) // they're explained in the help
+                        .validator(is_estimate)
+                        .help("How much work fits in a day. Defaults to daily_capacity from the config [OPTIONAL]"),
+                ),
+        )
+        // Add a template subcommand that adds a saved set of items for a
+        // process that is done again and again, or saves one from items in
+        // the list. The use is as follows:
+        // todo template list
+        // todo template apply release --anchor "next friday"
+        // todo template save release +release
+        .subcommand(
+            SubCommand::with_name("template")
+                .version("0.0.1")
+                .author(ME)
+                .about("Add todo items from templates of repeated processes")
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List the saved templates"),
+                )
+                .subcommand(
+                    SubCommand::with_name("apply")
+                        .about("Add the items of a template, due relative to an anchor day")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the template")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("anchor")
+                                .long("anchor")
+                                .takes_value(true)
+                                .help("The day the template's offsets count from, eg. 'next friday'. Defaults to today [OPTIONAL]"),
+                        )
+                        .arg(tz_arg("Read dates in this timezone, eg. America/New_York or UTC. Defaults to timezone from the config [OPTIONAL]"))
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .long("dry-run")
+                                .help("Show the todo items that would be added without saving them"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("save")
+                        .about("Save a group of todo items as a template")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the template")
+                                .required(true),
+                        )
+                        .args(&selector_args())
+                        .arg(
+                            Arg::with_name("anchor")
+                                .long("anchor")
+                                .takes_value(true)
+                                .help("The day to count due dates from. Defaults to the last day an item is due [OPTIONAL]"),
+                        )
+                        .arg(
+                            Arg::with_name("force")
+                                .short("f")
+                                .long("force")
+                                .help("Replace a template that already has the name"),
+                        ),
+                ),
+        )
+        // Add a priorities subcommand that shows how the priorities of the
+        // list are written or switches to another scheme. The use is as
+        // follows:
+        // todo priorities
+        // todo priorities letters
+        .subcommand(
+            SubCommand::with_name("priorities")
+                .version("0.0.1")
+                .author(ME)
+                .about("Show or change how the priorities of the todo list are written")
+                .arg(
+                    Arg::with_name("scheme")
+                        .help("The scheme to switch to, converting every priority to it [OPTIONAL]")
+                        .possible_values(&["numeric", "letters", "named"]),
                 ),
         )
         // Get the information from the command line.
@@ -96,230 +563,2342 @@ fn main() {
     if let Some(matches) = matches.subcommand_matches("view") {
         view_items(matches);
     }
-}
 
-/// # Priority Option Validator.
-/// Ensures that the Priority option is an i8 and is also within
-/// the bounds of 0 - 9. Meant to be used only with the clap Arg object
-fn is_priority(val: String) -> Result<(), String> {
-    // Try and parse the priority into an i8 to check its type
-    let priority = match val.parse::<i8>() {
-        // If it parsed then just return it
-        Ok(priority) => priority,
-        // If it didn't parse then return a reason for the error to the user
-        Err(_) => return Err(String::from(
-            "Priority needs to be an integer within the bounds 0 and 9. Input was not an integer.",
-        )),
-    };
+    // Agenda subcommands to view todo items by when they are due
+    if let Some(matches) = matches.subcommand_matches("today") {
+        agenda(matches, &[DueBand::Overdue, DueBand::Today], None, false);
+    }
+    if let Some(matches) = matches.subcommand_matches("upcoming") {
+        // The validator guarantees the days parse
+        let days = matches
+            .value_of("days")
+            .map(|days| days.parse::<u32>().unwrap());
+        agenda(matches, agenda::ALL_BANDS, days, days.is_none());
+    }
+    if let Some(matches) = matches.subcommand_matches("overdue") {
+        agenda(matches, &[DueBand::Overdue], None, false);
+    }
 
-    // Check the bounds of the priority
-    if priority > 9 || priority < 0 {
-        // If the priority is outside of good bounds then alert the user
-        return Err(String::from(
-            "Priority needs to be an integer within the bounds 0 and 9. Input was outside bounds.",
-        ));
+    // Calendar subcommand to view when todo items are due
+    if let Some(matches) = matches.subcommand_matches("cal") {
+        calendar(matches);
     }
 
-    // If this point is reached then the priority is valid
-    Ok(())
-}
+    // Archive subcommand to move done items out of the data file
+    if let Some(matches) = matches.subcommand_matches("archive") {
+        archive(matches);
+    }
 
-/// # New Item Handler
-/// Handles adding a new item to the todo list data file.
-///
-/// # Args
-///     - args: Arguments from the Clap CLI application
-fn new_item(args: &ArgMatches) {
-    // If the new subcommand is run then the "item" argument is always
-    // present. We do not need to worry about panicing
-    let item = args.value_of("item").unwrap().to_string();
+    // Done subcommand to mark todo items as done
+    if let Some(matches) = matches.subcommand_matches("done") {
+        done_item(matches);
+    }
 
-    // Get the priority value if there is one. Because of the validator
-    // we can assume that if the priority exists then it is a valid priority
-    let priority = match args.value_of("priority") {
-        Some(priority) => priority.parse::<i8>().ok(),
-        None => None,
-    };
+    // Edit subcommand to change todo items
+    if let Some(matches) = matches.subcommand_matches("edit") {
+        edit_items(matches);
+    }
 
-    // Try and parse the due date if one exists
-    let due = match args.value_of("due") {
-        Some(due) => {
-            // Try and parse the date string using the chrono_english lib
-            match parse_date_string(due, Local::now(), Dialect::Us) {
-                Ok(due) => Some(due),
-                Err(_) => {
-                    error!("Unable to parse due date. Please try again.");
-                    return;
-                }
-            }
-        }
-        None => None,
-    };
+    // Snooze and reschedule subcommands to move due dates
+    if let Some(matches) = matches.subcommand_matches("snooze") {
+        move_items(matches, true);
+    }
+    if let Some(matches) = matches.subcommand_matches("reschedule") {
+        move_items(matches, false);
+    }
 
-    // Stuff a TodoItem with the information
-    let todo_item = todo_item::TodoItem {
-        priority,
-        item,
-        due,
-    };
+    // Start subcommand to start tracking time
+    if let Some(matches) = matches.subcommand_matches("start") {
+        start_item(matches);
+    }
 
-    // Save the todo item to the data file
-    todo_item.save();
-}
+    // Stop subcommand to stop tracking time
+    if matches.subcommand_matches("stop").is_some() {
+        stop_item();
+    }
 
-/// # View Todo Items Handler
-/// Show all of the todo items currently stored
-///
-/// ## Args
-///     args: Clap CLI arguments
-fn view_items(args: &ArgMatches) {
-    // Define a gradient for the priorities. This goes from
-    // Red as priority 0 to green as priority 9
-    let priority_colors = vec![
-        color::Rgb(180, 19, 19),
-        color::Rgb(162, 31, 22),
-        color::Rgb(144, 44, 26),
-        color::Rgb(126, 57, 30),
-        color::Rgb(108, 70, 34),
-        color::Rgb(91, 83, 38),
-        color::Rgb(73, 96, 42),
-        color::Rgb(55, 109, 46),
-        color::Rgb(37, 122, 50),
-        color::Rgb(20, 135, 54),
-    ];
-
-    // Gather the todo items
-    let mut todo_items = todo_item::TodoItem::get_items();
-
-    // If there are no todo items then print that and exit
-    if todo_items.len() == 0 {
-        println!("There are no todo items! Add one with `todo new [TODO ITEM]` and try aagain");
-        return;
+    // Open subcommand to open the links of an item
+    if let Some(matches) = matches.subcommand_matches("open") {
+        open_links(matches);
     }
 
-    // Check the sort parameter to see if the todo items need to be sorted. Both
-    // the due date and the priority are optional values and they are stored in
-    // Option<> types. When Rust sorts using the cmp value the sorting is done
-    // such that these types propogate to the front of the vector. This is not
-    // a logical behavior for this application as it would sort those items
-    // without a priority before those with. Because of this the sorting needs
-    // to be customized as to make the None appear at the end of the vector.
-    match args.value_of("sort") {
-        // Sort by due date ascending
-        Some("d") | Some("da") => {
-            todo_items.sort_unstable_by(|a, b| {
-                if let None = b.due {
-                    if let None = a.due {
-                        Ordering::Equal
-                    } else {
-                        Ordering::Less
-                    }
-                } else {
-                    a.due.cmp(&b.due)
-                }
-            });
-        }
-        // Sort by priority ascending
-        Some("p") | Some("pa") => {
-            todo_items.sort_unstable_by(|a, b| {
-                if let None = b.priority {
-                    if let None = a.priority {
-                        Ordering::Equal
-                    } else {
-                        Ordering::Less
-                    }
-                } else {
-                    a.priority.cmp(&b.priority)
-                }
-            });
-        }
-        // Sort by due date descending
-        Some("dd") => {
-            todo_items.sort_unstable_by(|a, b| {
-                if let None = b.due {
-                    if let None = a.due {
-                        Ordering::Equal
-                    } else {
-                        Ordering::Less
-                    }
-                } else {
-                    b.due.cmp(&a.due)
-                }
-            });
-        }
-        // Sort by priority descending
-        Some("pd") => {
-            todo_items.sort_unstable_by(|a, b| {
-                if let None = b.priority {
-                    if let None = a.priority {
-                        Ordering::Equal
-                    } else {
-                        Ordering::Less
-                    }
-                } else {
-                    b.priority.cmp(&a.priority)
-                }
-            });
-        }
-        _ => {}
-    };
+    // Timesheet subcommand to report tracked time
+    if let Some(matches) = matches.subcommand_matches("timesheet") {
+        timesheet(matches);
+    }
 
-    // Create a new pretty print table
-    let mut table = Table::new();
-    table.add_row(row![b => "PRIORITY", "DUE DATE", "TODO"]);
-
-    // Iterate through all todo items in the file
-    for todo_item in todo_items {
-        // Create a new cell list for this todo item
-        let mut row: Vec<Cell> = vec![];
-
-        // Print the priority if one exists
-        if let Some(priority) = todo_item.priority {
-            // Because these todo items are being grabbed straight from the
-            // file and we are indexing an array with their priorityies we
-            // need to be extra careful that we won't go out of bounds
-            if priority > 9 || priority < 0 {
-                error!("Invalid priority.");
-                continue;
-            }
+    // Workload subcommand to forecast estimated work
+    if let Some(matches) = matches.subcommand_matches("workload") {
+        workload(matches);
+    }
 
-            // Pretty print the priority
-            row.push(
-                Cell::new(&format!(
-                    "{}{}{}{}{}",
-                    style::Bold,
-                    color::Fg(priority_colors[priority as usize]),
-                    priority,
-                    color::Fg(color::Reset),
-                    style::Reset,
-                ))
-                .style_spec("c"),
-            );
-        } else {
-            row.push(Cell::new(""));
-        }
+    // Doctor subcommand to check and repair the data file
+    if let Some(matches) = matches.subcommand_matches("doctor") {
+        doctor(matches);
+    }
 
-        // Pretty print the date if one exists
-        if let Some(due) = todo_item.due {
-            // Check if the due date is before the current date. If it is then set the
-            // styling to be a bright red color.
-            let style_string = if Local::now() > due { "Fr" } else { "" };
+    // Import lines subcommand to add an item per line of a file
+    if let Some(matches) = matches.subcommand_matches("import-lines") {
+        import_lines(matches);
+    }
 
-            // Add the nicely formatted date to the row
-            row.push(
-                Cell::new(&format!("{}", due.format("%m/%d/%y %I:%M%p"))).style_spec(style_string),
-            );
-        } else {
-            row.push(Cell::new(""));
+    // Template subcommands to add items from templates and save them
+    if let Some(matches) = matches.subcommand_matches("template") {
+        template(matches);
+    }
+
+    // Priorities subcommand to show or change the priority scheme
+    if let Some(matches) = matches.subcommand_matches("priorities") {
+        priorities(matches);
+    }
+}
+
+/// # Display Arguments
+/// The arguments shared by every subcommand that shows a list of todo items,
+/// which choose how the items are sorted and printed
+fn display_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("sort")
+            .help("Sorting the todo items.")
+            .long_help("Used to sort the todo items. Takes a comma separated list of fields to sort by in order, each optionally starting with a '-' to sort descending, eg. 'due,-priority,text'. The fields are id, priority, due, text, created, completed, wait, estimate, spent and urgency. Items missing a field always come last. Defaults to '-urgency', the most urgent items first. The short forms 'd', 'dd', 'p', 'pd' and 'u' are also accepted.")
+            .takes_value(true)
+            .short("s")
+            .long("sort")
+            .allow_hyphen_values(true)
+            .validator(is_sort_spec),
+        Arg::with_name("format")
+            .help("How to print the todo items")
+            .long_help("How to print the todo items. 'table' draws a table of the usual columns, 'plain' prints one tab separated line of id, priority, due date and text per item for grepping and other tools, 'compact' is the table without borders and 'wide' is a table of every field. Defaults to 'table'.")
+            .takes_value(true)
+            .short("f")
+            .long("format")
+            .validator(is_format),
+        color_arg(),
+        Arg::with_name("dates")
+            .help("How to show dates")
+            .long_help("How to show dates in every column. 'absolute' shows the date and time, 'relative' shows how far away it is like 'in 3h', 'tomorrow 9am' or '2 days overdue', and 'both' shows both. Defaults to the dates.due, dates.created and dates.completed config settings, which are 'absolute' unless changed.")
+            .takes_value(true)
+            .long("dates")
+            .possible_values(&["absolute", "relative", "both"]),
+        Arg::with_name("truncate")
+            .long("truncate")
+            .help("Cut todo text that doesn't fit in the terminal short instead of wrapping it"),
+        Arg::with_name("limit")
+            .long("limit")
+            .takes_value(true)
+            .validator(is_count)
+            .help("Show at most this many items [OPTIONAL]"),
+        Arg::with_name("offset")
+            .long("offset")
+            .takes_value(true)
+            .validator(is_count)
+            .help("Skip this many items before showing any, to page through them with --limit [OPTIONAL]"),
+        Arg::with_name("no-pager")
+            .long("no-pager")
+            .help("Print everything even when it doesn't fit in the terminal, instead of using a pager"),
+        tz_arg("Show dates in this timezone, eg. America/New_York or UTC. Defaults to timezone from the config [OPTIONAL]"),
+    ]
+}
+
+/// # Color Argument
+/// The argument that chooses when output is colored
+fn color_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("color")
+        .help("When to color the output")
+        .long_help("When to color the output. 'auto' colors output going to a terminal unless the NO_COLOR environment variable is set. Defaults to 'auto'.")
+        .takes_value(true)
+        .long("color")
+        .possible_values(&["always", "never", "auto"])
+        .default_value("auto")
+}
+
+/// # Parse Priority
+/// Reads a priority in the scheme of the todo list, alerting the user if it
+/// isn't part of the scheme. Priorities can't be checked by a validator
+/// since the scheme is only known once the list is open.
+///
+/// ## Args
+///     - priority: the priority from the command line
+///     - todo_list: the list the priority is for
+///
+/// ## Rets
+///     - Option with the rank of the priority, None if it isn't valid
+fn parse_priority(priority: &str, todo_list: &TodoList) -> Option<i8> {
+    match todo_list.scheme().parse(priority) {
+        Ok(priority) => Some(priority),
+        Err(e) => {
+            error!("{}.", e);
+            None
         }
+    }
+}
 
-        // End the line with the actual todo item
-        row.push(Cell::new(&todo_item.item));
+/// # Id Validator
+/// Ensures that an item id argument is a positive integer. Meant to be used
+/// only with the clap Arg object
+fn is_id(val: String) -> Result<(), String> {
+    match val.parse::<u32>() {
+        Ok(id) if id > 0 => Ok(()),
+        _ => Err(String::from("Id needs to be a positive integer.")),
+    }
+}
 
-        // Add row to table
-        table.add_row(Row::new(row));
+/// # Item Text Validator
+/// Ensures that todo text fits on one line, since each item is a single
+/// line of the data file. Meant to be used only with the clap Arg object
+fn is_item_text(val: String) -> Result<(), String> {
+    if val.contains(['\n', '\r']) {
+        Err(String::from("Todo text has to fit on one line."))
+    } else {
+        Ok(())
     }
+}
 
-    // Print the table
+/// # Or None Validator
+/// Accepts 'none' as well as anything the given validator accepts, for
+/// parameters that can remove a field.
+/// Meant to be used only with the clap Arg object
+fn or_none(val: String, validator: fn(String) -> Result<(), String>) -> Result<(), String> {
+    if val == "none" {
+        Ok(())
+    } else {
+        validator(val)
+    }
+}
+
+/// # Timezone Validator
+/// Ensures that a timezone is 'local' or a name from the IANA database.
+/// Meant to be used only with the clap Arg object
+fn is_zone(val: String) -> Result<(), String> {
+    val.parse::<Zone>().map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// # Count Validator
+/// Ensures that a count of days or items is a whole number.
+/// Meant to be used only with the clap Arg object
+fn is_count(val: String) -> Result<(), String> {
+    match val.parse::<u32>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("Needs to be a whole number.")),
+    }
+}
+
+/// # Forecast Days Validator
+/// Ensures that the number of days to forecast is a positive whole number.
+/// Meant to be used only with the clap Arg object
+fn is_forecast_days(val: String) -> Result<(), String> {
+    match val.parse::<u32>() {
+        Ok(days) if days > 0 => Ok(()),
+        _ => Err(String::from("--days needs to be a positive whole number.")),
+    }
+}
+
+/// # Offset Validator
+/// Ensures that an offset is a whole number, which may be negative.
+/// Meant to be used only with the clap Arg object
+fn is_offset(val: String) -> Result<(), String> {
+    match val.parse::<i32>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("Offset needs to be a whole number.")),
+    }
+}
+
+/// # Sort Spec Validator
+/// Ensures that the sort argument is a list of fields that can be sorted by.
+/// Meant to be used only with the clap Arg object
+fn is_sort_spec(val: String) -> Result<(), String> {
+    val.parse::<SortSpec>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// # Format Validator
+/// Ensures that the format argument names an output format.
+/// Meant to be used only with the clap Arg object
+fn is_format(val: String) -> Result<(), String> {
+    val.parse::<Format>().map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// # Estimate Validator
+/// Ensures that an estimate argument is an amount of hours and minutes.
+/// Meant to be used only with the clap Arg object
+fn is_estimate(val: String) -> Result<(), String> {
+    workload::parse_estimate(&val)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// # Allow Past Argument
+/// The argument that lets a due date be set in the past
+fn allow_past_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("allow-past")
+        .long("allow-past")
+        .help("Allow due dates that have already gone by")
+}
+
+/// # Parse Date Argument
+/// Parses a date given on the command line, alerting the user if it can't be
+/// understood.
+///
+/// ## Args
+///     - date: the date string from the command line
+///     - base: the time relative dates are counted from
+///     - config: the user's config, for the date dialect
+///     - zone: the timezone the date is typed in
+///
+/// ## Rets
+///     - Option with the date, None if it couldn't be parsed
+fn parse_date(
+    date: &str,
+    base: DateTime<Local>,
+    config: &Config,
+    zone: Zone,
+) -> Option<DateTime<Local>> {
+    match dates::parse_date(date, base, config.dialect, zone) {
+        Ok(date) => Some(date),
+        Err(e) => {
+            error!("{}.", e);
+            None
+        }
+    }
+}
+
+/// # Parse Due Date Argument
+/// Parses a due date given on the command line and echoes back how it was
+/// read. Dates that have gone by are turned down unless the allow-past
+/// parameter is given, and the user is asked if the date is right when the
+/// confirm_dates setting is on.
+///
+/// ## Args
+///     - args: Clap CLI arguments with the allow past and timezone arguments
+///     - date: the date string from the command line
+///     - now: the current time
+///     - config: the user's config
+///
+/// ## Rets
+///     - Option with the due date, whether it is all day and the timezone it
+///       was set in, None if it couldn't be parsed or wasn't wanted
+fn parse_due(
+    args: &ArgMatches,
+    date: &str,
+    now: DateTime<Local>,
+    config: &Config,
+) -> Option<(DateTime<Local>, bool, Option<Tz>)> {
+    let zone = zone(args, config);
+    let (due, all_day) = match dates::parse_due(date, now, config.dialect, zone) {
+        Ok(due) => due,
+        Err(e) => {
+            error!("{}.", e);
+            return None;
+        }
+    };
+    let read_as = if all_day {
+        format!(
+            "all day {} ({})",
+            dates::format_long_day(zone.at(due).date_naive()),
+            dates::format_relative_day(zone.at(due).date_naive(), zone.at(now).date_naive(), false)
+        )
+    } else {
+        format!(
+            "{} ({})",
+            dates::format_long(zone.at(due)),
+            dates::format_relative(zone.at(due), zone.at(now), false)
+        )
+    };
+
+    if due < now && !args.is_present("allow-past") {
+        error!(
+            "'{}' was read as {}, which has gone by. Pass --allow-past to use it anyway.",
+            date, read_as
+        );
+        return None;
+    }
+
+    if config.confirm_dates {
+        if !confirm(&format!("Due {}?", read_as)) {
+            return None;
+        }
+    } else {
+        info!("Due {}.", read_as);
+    }
+
+    Some((due, all_day, zone.named()))
+}
+
+/// # Format Due Date
+/// Formats a due date in full in a timezone, or just its day if the item is
+/// due all day
+///
+/// ## Args
+///     - due: the due date
+///     - all_day: whether the item is due all day
+///     - set_in: the timezone the due date was set in, which the day of an
+///       item due all day is in
+///     - zone: the timezone to show a timed due date in
+fn format_due(due: DateTime<Local>, all_day: bool, set_in: Zone, zone: Zone) -> String {
+    if all_day {
+        dates::format_absolute_day(set_in.at(due).date_naive())
+    } else {
+        dates::format_absolute(zone.at(due))
+    }
+}
+
+/// # Timezone
+/// Gets the timezone dates are typed and shown in from the tz parameter, or
+/// the config if it isn't given. The validator guarantees the parameter
+/// parses
+///
+/// ## Args
+///     - args: Clap CLI arguments
+///     - config: the user's config
+fn zone(args: &ArgMatches, config: &Config) -> Zone {
+    args.value_of("tz")
+        .map_or(config.timezone, |zone| zone.parse::<Zone>().unwrap())
+}
+
+/// # Timezone Argument
+/// The argument that picks the timezone dates are typed or shown in
+///
+/// ## Args
+///     - help: what the timezone is used for
+fn tz_arg<'a, 'b>(help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name("tz")
+        .long("tz")
+        .takes_value(true)
+        .validator(is_zone)
+        .help(help)
+}
+
+/// # Link Argument
+/// The argument that gives links for todo items, which can be repeated
+///
+/// ## Args
+///     - help: what the links are for
+fn link_arg<'a, 'b>(help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name("link")
+        .short("l")
+        .long("link")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help(help)
+}
+
+/// # Resolve Links
+/// Checks the links given on the command line, making paths absolute,
+/// alerting the user if one is wrong.
+///
+/// ## Args
+///     - links: the links as they were typed
+///
+/// ## Rets
+///     - Option with the links to store, None if one of them is wrong
+fn resolve_links<'a>(links: impl Iterator<Item = &'a str>) -> Option<Vec<String>> {
+    let base = match env::current_dir() {
+        Ok(base) => base,
+        Err(e) => {
+            error!("Couldn't get the current directory. {}.", e);
+            return None;
+        }
+    };
+    let mut resolved = Vec::new();
+    for link in links {
+        match link::resolve(link, &base) {
+            Ok(link) => resolved.push(link),
+            Err(e) => {
+                error!("{}.", e);
+                return None;
+            }
+        }
+    }
+    Some(resolved)
+}
+
+/// # New Item Handler
+/// Handles adding a new item to the todo list data file.
+///
+/// # Args
+///     - args: Arguments from the Clap CLI application
+fn new_item(args: &ArgMatches) {
+    // If the new subcommand is run then the "item" argument is always
+    // present. We do not need to worry about panicing
+    let item = args.value_of("item").unwrap().to_string();
+
+    // A lone hyphen reads the items from stdin instead
+    if item == "-" {
+        let single = ["priority", "due", "wait", "estimate", "depends", "link"];
+        if single.iter().any(|name| args.is_present(name)) {
+            error!("Write priorities and due dates on each line when adding from stdin.");
+            return;
+        }
+        let mut text = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut text) {
+            error!("Couldn't read stdin. {}.", e);
+            return;
+        }
+        add_lines(args, &text, "stdin");
+        return;
+    }
+
+    // The config says how to read dates
+    let now = Local::now();
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+
+    // Open the todo list that the item will be added to. Priorities are
+    // written in its scheme
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    // In quick mode the priority and due date can be written in the text.
+    // The flags are used over anything found there
+    let quick = if args.is_present("quick") {
+        match QuickAdd::parse(
+            &item,
+            now,
+            config.dialect,
+            zone(args, &config),
+            todo_list.scheme(),
+        ) {
+            Ok(quick) => quick,
+            Err(e) => {
+                error!("{}.", e);
+                return;
+            }
+        }
+    } else {
+        QuickAdd {
+            text: item,
+            ..QuickAdd::default()
+        }
+    };
+
+    // Try and parse the due date if one exists
+    let (due, all_day, tz) = match args.value_of("due").or(quick.due.as_deref()) {
+        Some(due) => match parse_due(args, due, now, &config) {
+            Some((due, all_day, tz)) => (Some(due), all_day, tz),
+            None => return,
+        },
+        None => (None, false, None),
+    };
+
+    // The wait date is parsed the same way as the due date
+    let wait = match args.value_of("wait") {
+        Some(wait) => match parse_date(wait, now, &config, zone(args, &config)) {
+            Some(wait) => Some(wait),
+            None => return,
+        },
+        None => None,
+    };
+
+    // Get the estimate if there is one. The validator guarantees it parses
+    let estimate = args
+        .value_of("estimate")
+        .and_then(|estimate| workload::parse_estimate(estimate).ok());
+
+    // Get the priority value if there is one. It is written in the scheme
+    // of the list
+    let priority = match args.value_of("priority") {
+        Some(priority) => match parse_priority(priority, &todo_list) {
+            Some(priority) => Some(priority),
+            None => return,
+        },
+        None => quick.priority,
+    };
+
+    // The validator guarantees the ids parse but they also have to exist
+    let mut depends = Vec::new();
+    for id in args.values_of("depends").into_iter().flatten() {
+        let id = id.parse::<u32>().unwrap();
+        if todo_list.get(id).is_none() {
+            error!("There is no todo item with id {} to depend on.", id);
+            return;
+        }
+        depends.push(id);
+    }
+
+    // Links from the flags come after any found in the text
+    let mut links = quick.links;
+    match resolve_links(args.values_of("link").into_iter().flatten()) {
+        Some(resolved) => links.extend(resolved),
+        None => return,
+    }
+
+    // Stuff a TodoItem with the information and save it to the data file
+    let item = TodoItem {
+        id: todo_list.next_id(),
+        priority,
+        item: quick.text,
+        due,
+        all_day,
+        tz,
+        created: Some(now),
+        wait,
+        estimate,
+        depends,
+        links,
+        ..TodoItem::default()
+    };
+    if args.is_present("dry-run") {
+        show_item(&item, &todo_list, zone(args, &config));
+        info!("Dry run, nothing was saved.");
+        return;
+    }
+
+    todo_list.add(item);
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+    }
+}
+
+/// # Import Lines Handler
+/// Handles adding an item for each line of a file
+///
+/// ## Args
+///     - args: Clap CLI arguments with the file
+fn import_lines(args: &ArgMatches) {
+    // Clap guarantees the file is given
+    let path = args.value_of("file").unwrap();
+    match fs::read_to_string(path) {
+        Ok(text) => add_lines(args, &text, path),
+        Err(e) => {
+            error!("Couldn't read {}. {}.", path, e);
+        }
+    }
+}
+
+/// # Add Lines
+/// Adds a todo item for each line of some text, reading priorities and due
+/// dates from each line the way quick add does. Blank lines and lines
+/// starting with '#' are skipped. Every line is checked before anything is
+/// added so that a mistake on one line leaves the list as it was, and the
+/// items are saved in a single write.
+///
+/// ## Args
+///     - args: Clap CLI arguments with the timezone and dry run arguments
+///     - text: the lines to add
+///     - source: where the lines came from, for error messages
+fn add_lines(args: &ArgMatches, text: &str, source: &str) {
+    let now = Local::now();
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match read_line(args, line, now, &config, &todo_list) {
+            Ok(item) => items.push(item),
+            Err(e) => errors.push((number + 1, e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        for (number, e) in &errors {
+            error!("{} line {}: {}.", source, number, e);
+        }
+        error!(
+            "Found problems on {} line(s). Nothing was added.",
+            errors.len()
+        );
+        return;
+    }
+    if items.is_empty() {
+        info!("No todo items to add in {}.", source);
+        return;
+    }
+
+    // Items are numbered the way they will be once they are added
+    let first_id = todo_list.next_id();
+    for (index, item) in items.iter_mut().enumerate() {
+        item.id = first_id + index as u32;
+    }
+
+    if args.is_present("dry-run") {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            show_item(item, &todo_list, zone(args, &config));
+        }
+        info!("Dry run, nothing was saved.");
+        return;
+    }
+
+    let count = items.len();
+    for item in items {
+        todo_list.add(item);
+    }
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+    info!("Added {} todo item(s).", count);
+}
+
+/// # Read Line
+/// Reads a single line of text into a todo item the way quick add does,
+/// without asking about or echoing the due date. Quick add never reads a
+/// date that has gone by so there is nothing to check it against.
+///
+/// ## Args
+///     - args: Clap CLI arguments with the timezone argument
+///     - line: the line to read
+///     - now: the current time
+///     - config: the user's config
+///     - todo_list: the list the item is for, for its priority scheme
+///
+/// ## Rets
+///     - Result with the item or a description of what is wrong with the
+///       line
+fn read_line(
+    args: &ArgMatches,
+    line: &str,
+    now: DateTime<Local>,
+    config: &Config,
+    todo_list: &TodoList,
+) -> Result<TodoItem, String> {
+    let zone = zone(args, config);
+    let quick = QuickAdd::parse(line, now, config.dialect, zone, todo_list.scheme())
+        .map_err(|e| e.to_string())?;
+
+    let (due, all_day, tz) = match &quick.due {
+        Some(date) => {
+            let (due, all_day) =
+                dates::parse_due(date, now, config.dialect, zone).map_err(|e| e.to_string())?;
+            (Some(due), all_day, zone.named())
+        }
+        None => (None, false, None),
+    };
+
+    Ok(TodoItem {
+        priority: quick.priority,
+        item: quick.text,
+        due,
+        all_day,
+        tz,
+        created: Some(now),
+        links: quick.links,
+        ..TodoItem::default()
+    })
+}
+
+/// # Show Item
+/// Prints every field of a todo item that is set, one per line, followed by
+/// the line it is written as in the data file
+///
+/// ## Args
+///     - item: the todo item to show
+///     - todo_list: the list the item is for, for its priority scheme
+///     - zone: the timezone to show dates in
+fn show_item(item: &TodoItem, todo_list: &TodoList, zone: Zone) {
+    let mut fields = vec![("id", item.id.to_string()), ("text", item.item.clone())];
+    if let Some(priority) = item.priority {
+        fields.push(("priority", todo_list.scheme().label(priority)));
+    }
+    if let Some(due) = item.due {
+        let due = if item.all_day {
+            format!(
+                "all day {}",
+                dates::format_long_day(item.zone().at(due).date_naive())
+            )
+        } else {
+            dates::format_long(zone.at(due))
+        };
+        fields.push(("due", due));
+    }
+    if let Some(wait) = item.wait {
+        fields.push(("wait", dates::format_long(zone.at(wait))));
+    }
+    if let Some(estimate) = item.estimate {
+        fields.push(("estimate", format_duration(estimate)));
+    }
+    if !item.depends.is_empty() {
+        let depends: Vec<String> = item.depends.iter().map(u32::to_string).collect();
+        fields.push(("depends", depends.join(", ")));
+    }
+    if !item.links.is_empty() {
+        fields.push(("links", item.links.join(", ")));
+    }
+    if !item.projects().is_empty() {
+        fields.push(("projects", item.projects().join(", ")));
+    }
+    if !item.contexts().is_empty() {
+        fields.push(("contexts", item.contexts().join(", ")));
+    }
+
+    for (name, value) in fields {
+        println!("{:>9}: {}", name, value);
+    }
+    println!("{:>9}: {}", "line", item);
+}
+
+/// # Open Todo List
+/// Opens the default todo list, alerting the user if the data file has to be
+/// created or can't be read.
+///
+/// ## Rets
+///     - Option with the todo list, None if it couldn't be opened
+fn open_list() -> Option<TodoList> {
+    let path = match TodoList::default_path() {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Couldn't open data file. {}", e);
+            return None;
+        }
+    };
+
+    // Alert the user that the file is being created
+    // TODO: Might make sense to ask for confirmation
+    if !path.exists() {
+        info!("File doesn't exist. Creating it.");
+    }
+
+    let mut todo_list = match TodoList::open(path) {
+        Ok(todo_list) => todo_list,
+        Err(e) => {
+            error!("Couldn't open data file. {}", e);
+            return None;
+        }
+    };
+
+    // Let the user know if their data file was upgraded
+    if let Some(upgrade) = todo_list.upgrade() {
+        info!(
+            "Upgraded data file from format version {} to {}. A backup was saved to {}.",
+            upgrade.from,
+            upgrade.to,
+            upgrade.backup.display()
+        );
+    }
+
+    // Archive items that were completed long enough ago, if the config asks
+    // for it
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Couldn't read config file. {}", e);
+            return None;
+        }
+    };
+    if let Some(days) = config.auto_archive {
+        match todo_list.archive(Local::now() - Duration::days(i64::from(days))) {
+            Ok(archived) if !archived.is_empty() => {
+                info!(
+                    "Archived {} items completed more than {} days ago.",
+                    archived.len(),
+                    days
+                );
+            }
+            Ok(_) => (),
+            Err(e) => {
+                error!("Couldn't archive items. {}", e);
+                return None;
+            }
+        }
+    }
+
+    Some(todo_list)
+}
+
+/// # Load Items
+/// Opens the todo list and config and sorts the items for display by the
+/// sort parameter
+///
+/// ## Args
+///     args: Clap CLI arguments with the display arguments
+///     default_sort: how to sort the items if there isn't a sort parameter
+///     now: the current time
+///
+/// ## Rets
+///     The sorted todo list and the config, or None if either couldn't be read
+fn load_items(
+    args: &ArgMatches,
+    default_sort: SortSpec,
+    now: DateTime<Local>,
+) -> Option<(TodoList, Config)> {
+    // Gather the todo items, or the archived items if asked for
+    let mut todo_list = open_list()?;
+    if args.is_present("archived") {
+        todo_list = match todo_list.open_archive() {
+            Ok(archive) => archive,
+            Err(e) => {
+                error!("Couldn't open archive file. {}", e);
+                return None;
+            }
+        };
+    }
+
+    // Read the urgency coefficients and date styles from the config
+    let config = open_config()?;
+
+    // Sort the todo items by the sort parameter. The validator guarantees
+    // the parameter parses
+    let spec = args
+        .value_of("sort")
+        .map_or(default_sort, |spec| spec.parse::<SortSpec>().unwrap());
+    todo_list.sort(&spec, &config.urgency, now);
+
+    Some((todo_list, config))
+}
+
+/// # Render Context
+/// Builds the render context from the display arguments. Output is colored
+/// unless asked not to or it isn't going to a terminal, tables are fit to
+/// the terminal and dates are shown in the chosen style. Clap guarantees the
+/// color and dates parameters are one of the choices
+///
+/// ## Args
+///     args: Clap CLI arguments with the display arguments
+///     todo_list: the todo list being shown
+///     config: the user's config
+///     now: the current time
+fn render_context<'a>(
+    args: &ArgMatches,
+    todo_list: &TodoList,
+    config: &'a Config,
+    now: DateTime<Local>,
+) -> render::Context<'a> {
+    render::Context {
+        now,
+        scorer: Scorer::new(todo_list.items(), &config.urgency, todo_list.scheme(), now),
+        color: args
+            .value_of("color")
+            .unwrap()
+            .parse::<ColorChoice>()
+            .unwrap()
+            .enabled(),
+        width: render::terminal_width(),
+        truncate: args.is_present("truncate"),
+        dates: args.value_of("dates").map_or(config.dates, |style| {
+            DateDisplay::all(style.parse::<DateStyle>().unwrap())
+        }),
+        zone: zone(args, config),
+        scheme: todo_list.scheme(),
+    }
+}
+
+/// # Output Format
+/// Gets the output format from the format parameter. The validator
+/// guarantees the parameter parses
+fn output_format(args: &ArgMatches) -> Format {
+    args.value_of("format")
+        .map_or(Format::Table, |format| format.parse::<Format>().unwrap())
+}
+
+/// # View Todo Items Handler
+/// Show all of the todo items currently stored
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn view_items(args: &ArgMatches) {
+    // Gather the todo items, most urgent first unless asked otherwise
+    let now = Local::now();
+    let (todo_list, config) = match load_items(args, SortSpec::default(), now) {
+        Some(loaded) => loaded,
+        None => return,
+    };
+
+    // If there are no todo items then print that and exit. Items that are
+    // done or waiting are only shown when asked for, or when looking through
+    // the archive where every item is done. --waiting picks out the waiting
+    // items before --all adds the done ones back
+    let show_all = args.is_present("all") || args.is_present("archived");
+    let waiting = args.is_present("waiting");
+    let filter = Filter {
+        text: args.value_of("search").map(String::from),
+        ..Filter::default()
+    };
+    let items: Vec<&TodoItem> = todo_list
+        .filter(&filter)
+        .into_iter()
+        .filter(|item| show_all || !item.is_done())
+        .filter(|item| {
+            if waiting {
+                item.is_waiting(now)
+            } else {
+                show_all || !item.is_waiting(now)
+            }
+        })
+        .collect();
+    if items.is_empty() {
+        if waiting {
+            println!("No todo items are waiting to start.");
+        } else if args.is_present("archived") || args.is_present("search") {
+            println!("No todo items found.");
+        } else {
+            println!("There are no todo items! Add one with `todo new [TODO ITEM]` and try aagain");
+        }
+        return;
+    }
+
+    let format = output_format(args);
+    let context = render_context(args, &todo_list, &config, now);
+    let mut output = String::new();
+
+    // Show the item that time is being tracked against, except in the plain
+    // format where every line is an item
+    if let Some((active, interval)) = todo_list.active().filter(|_| format != Format::Plain) {
+        let working = format!(
+            "Working on {} ({}) for {}",
+            active.id,
+            active.item,
+            format_duration(interval.duration(now))
+        );
+        output.push_str(&context.paint(&working, &style::Bold, &style::Reset));
+        output.push('\n');
+    }
+
+    // Render the page of items that are shown
+    let range = page_range(args, items.len());
+    let shown = &items[range.clone()];
+    output.push_str(&format.renderer().render(shown, &context));
+
+    if format != Format::Plain {
+        if let Some(footer) = footer(shown.len(), items.len(), todo_list.len()) {
+            output.push_str(&footer);
+        }
+    }
+    page(args, &output);
+}
+
+/// # Page Range
+/// Works out which of the items to show from the offset and limit
+/// parameters. The validators guarantee the parameters parse
+///
+/// ## Args
+///     args: Clap CLI arguments with the display arguments
+///     count: how many items there are to show
+fn page_range(args: &ArgMatches, count: usize) -> Range<usize> {
+    let offset = args
+        .value_of("offset")
+        .map_or(0, |offset| offset.parse::<usize>().unwrap())
+        .min(count);
+    let limit = args
+        .value_of("limit")
+        .map_or(count, |limit| limit.parse::<usize>().unwrap());
+    offset..count.min(offset.saturating_add(limit))
+}
+
+/// # Footer
+/// Describes how many items weren't shown, either because they were cut off
+/// by the limit and offset or because they were filtered out. None if every
+/// item was shown.
+///
+/// ## Args
+///     shown: how many items were shown
+///     matching: how many items there were to show before the limit and offset
+///     total: how many items are in the list
+fn footer(shown: usize, matching: usize, total: usize) -> Option<String> {
+    let mut hidden = Vec::new();
+    if matching > shown {
+        hidden.push(format!("{} by --limit and --offset", matching - shown));
+    }
+    if total > matching {
+        hidden.push(format!("{} filtered out", total - matching));
+    }
+
+    if hidden.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "Showing {} of {} items. Hidden: {}.\n",
+            shown,
+            total,
+            hidden.join(", ")
+        ))
+    }
+}
+
+/// # Page Output
+/// Prints output, through a pager if it is going to a terminal and has more
+/// lines than fit. The pager is $PAGER, or `less -R` if that isn't set, and
+/// the output is printed directly if the pager can't be started.
+///
+/// ## Args
+///     args: Clap CLI arguments with the display arguments
+///     output: the text to print
+fn page(args: &ArgMatches, output: &str) {
+    let fits = render::terminal_height().is_none_or(|height| output.lines().count() < height);
+    if fits || args.is_present("no-pager") {
+        print!("{}", output);
+        return;
+    }
+
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| String::from("less -R"));
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap();
+    match Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(mut child) => {
+            // The pager closing early isn't an error worth reporting
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(output.as_bytes());
+            }
+            let _ = child.wait();
+        }
+        Err(_) => print!("{}", output),
+    }
+}
+
+/// # Agenda Handler
+/// Shows the todo items that aren't done in sections by how soon they are
+/// due, each under a heading with how many items it has. Items are sorted by
+/// due date within a section unless asked otherwise.
+///
+/// ## Args
+///     args: Clap CLI arguments
+///     bands: the sections to show
+///     days: leave out items due more than this many days after today
+///     undated: show a section of items without a due date
+fn agenda(args: &ArgMatches, bands: &[DueBand], days: Option<u32>, undated: bool) {
+    let now = Local::now();
+    let default_sort = "due,-urgency".parse::<SortSpec>().unwrap();
+    let (todo_list, config) = match load_items(args, default_sort, now) {
+        Some(loaded) => loaded,
+        None => return,
+    };
+    let zone = zone(args, &config);
+    let until = days.map(|days| agenda::days_from_now(now, days, zone));
+
+    let sections = agenda::agenda(todo_list.items(), now, bands, until, undated, zone);
+    if sections.is_empty() {
+        println!("Nothing to show. You're all caught up!");
+        return;
+    }
+
+    // Work out which items to show across all of the sections
+    let matching: usize = sections.iter().map(|section| section.items.len()).sum();
+    let range = page_range(args, matching);
+
+    let format = output_format(args);
+    let renderer = format.renderer();
+    let context = render_context(args, &todo_list, &config, now);
+    let mut output = String::new();
+    let mut index = 0;
+    for section in &sections {
+        // Skip the items before and after the page
+        let start = index;
+        index += section.items.len();
+        let shown: Vec<&TodoItem> = section
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(offset, _)| range.contains(&(start + offset)))
+            .map(|(_, item)| *item)
+            .collect();
+        if shown.is_empty() {
+            continue;
+        }
+
+        // Separate the sections with a blank line
+        if !output.is_empty() {
+            output.push('\n');
+        }
+
+        // Color the heading the same as the due dates in the section
+        let heading = if shown.len() == section.items.len() {
+            format!("{} ({})", section.title(), section.items.len())
+        } else {
+            format!(
+                "{} ({} of {})",
+                section.title(),
+                shown.len(),
+                section.items.len()
+            )
+        };
+        let heading = match section.band {
+            Some(band) => context.paint_band(&heading, band),
+            None => heading,
+        };
+        output.push_str(&context.paint(&heading, &style::Bold, &style::Reset));
+        output.push('\n');
+
+        output.push_str(&renderer.render(&shown, &context));
+    }
+
+    if format != Format::Plain {
+        if let Some(footer) = footer(range.len(), matching, todo_list.len()) {
+            if !output.is_empty() {
+                output.push('\n');
+            }
+            output.push_str(&footer);
+        }
+    }
+    page(args, &output);
+}
+
+/// # Calendar Handler
+/// Draws a calendar of the month or week with the items that aren't done on
+/// the day they are due. Each day shows how many items are due, or their
+/// titles with --titles, colored by the highest priority due that day.
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn calendar(args: &ArgMatches) {
+    // How many titles fit in a day before the rest are counted instead
+    const MAX_TITLES: usize = 4;
+
+    let now = Local::now();
+
+    // Clap guarantees the span is one of the choices and the validator
+    // guarantees the offset parses
+    let span = args.value_of("span").unwrap().parse::<Span>().unwrap();
+    let offset = args
+        .value_of("offset")
+        .map_or(0, |offset| offset.parse::<i32>().unwrap());
+
+    // Gather the todo items, most important first so that they are listed
+    // first on each day
+    let (todo_list, config) = match load_items(args, "priority,due".parse().unwrap(), now) {
+        Some(loaded) => loaded,
+        None => return,
+    };
+
+    // Days are the days in the timezone dates are shown in
+    let zone = zone(args, &config);
+    let today = zone.at(now).date_naive();
+    let anchor = match args.value_of("date") {
+        Some(date) => match parse_date(date, now, &config, zone) {
+            Some(date) => zone.at(date).date_naive(),
+            None => return,
+        },
+        None => today,
+    };
+    let context = render_context(args, &todo_list, &config, now);
+    let calendar = Calendar::new(todo_list.items(), span, anchor, offset, zone);
+
+    // Split the terminal between the seven days. Each column is padded by a
+    // space on either side and has a border before it
+    let width = render::terminal_width()
+        .map_or(14, |width| (width.saturating_sub(1) / 7).saturating_sub(3))
+        .max(6);
+
+    let mut table = Table::new();
+    table.set_titles(Row::new(
+        ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+            .iter()
+            .map(|day| Cell::new(&context.paint(day, &style::Bold, &style::Reset)))
+            .collect(),
+    ));
+
+    for week in &calendar.weeks {
+        let cells = week
+            .iter()
+            .map(|day| {
+                // Bold today and fade the days outside the month. The day is
+                // padded out so that every column is the same width
+                let number = day.date.format("%d").to_string();
+                let number = if day.date == today {
+                    format!("{}*", number)
+                } else {
+                    number
+                };
+                let number = format!("{:width$}", number, width = width);
+                let number = if day.date == today {
+                    context.paint(&number, &style::Bold, &style::Reset)
+                } else if !day.in_span {
+                    context.paint(&number, &style::Faint, &style::Reset)
+                } else {
+                    number
+                };
+                let mut lines = vec![number];
+
+                if args.is_present("titles") {
+                    for item in day.items.iter().take(MAX_TITLES) {
+                        let title = render::truncate(&item.item, width);
+                        lines.push(match item.priority {
+                            Some(priority) => context.paint_priority(&title, priority),
+                            None => title,
+                        });
+                    }
+                    if day.items.len() > MAX_TITLES {
+                        lines.push(format!("+{} more", day.items.len() - MAX_TITLES));
+                    }
+                } else if !day.items.is_empty() {
+                    let count = match day.items.len() {
+                        1 => "1 item".to_string(),
+                        count => format!("{} items", count),
+                    };
+                    lines.push(match day.highest_priority() {
+                        Some(priority) => context.paint_priority(&count, priority),
+                        None => count,
+                    });
+                }
+
+                Cell::new(&lines.join("\n"))
+            })
+            .collect();
+        table.add_row(Row::new(cells));
+    }
+
+    println!(
+        "{}",
+        context.paint(&calendar.title(), &style::Bold, &style::Reset)
+    );
+    print!("{}", table);
+}
+
+/// # Archive Handler
+/// Moves the items that are done to the archive file, or only those
+/// completed more than --days days ago
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn archive(args: &ArgMatches) {
+    // The validator guarantees the days parse
+    let days = args
+        .value_of("days")
+        .map_or(0, |days| days.parse::<i64>().unwrap());
+
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    match todo_list.archive(Local::now() - Duration::days(days)) {
+        Ok(archived) if archived.is_empty() => {
+            info!("There are no done items to archive.");
+        }
+        Ok(archived) => {
+            info!(
+                "Archived {} items to {}.",
+                archived.len(),
+                todo_list.archive_path().display()
+            );
+        }
+        Err(e) => {
+            error!("Couldn't archive items. {}", e);
+        }
+    }
+}
+
+/// # Doctor Handler
+/// Reports every problem in the data file with its line number. With --fix
+/// every problem is repaired, and with --interactive the user is asked
+/// about each line.
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn doctor(args: &ArgMatches) {
+    let path = match TodoList::default_path() {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Couldn't open data file. {}", e);
+            return;
+        }
+    };
+
+    let diagnoses = match doctor::examine(&path) {
+        Ok(diagnoses) => diagnoses,
+        Err(e) => {
+            error!("Couldn't check data file. {}", e);
+            return;
+        }
+    };
+
+    if diagnoses.is_empty() {
+        info!("No problems found in {}.", path.display());
+        return;
+    }
+
+    // Report every problem along with the line it is on
+    for diagnosis in &diagnoses {
+        for problem in &diagnosis.problems {
+            println!("line {}: {}", diagnosis.line, problem);
+        }
+    }
+
+    // Work out which lines to repair
+    let to_repair: Vec<Diagnosis> = if args.is_present("fix") {
+        diagnoses
+    } else if args.is_present("interactive") {
+        diagnoses
+            .into_iter()
+            .filter(|diagnosis| {
+                println!();
+                println!("line {}: {}", diagnosis.line, diagnosis.text);
+                confirm(&format!("Repair ({})?", diagnosis.repair))
+            })
+            .collect()
+    } else {
+        println!(
+            "Found problems on {} line(s). Run `todo doctor --fix` to repair them all or `todo doctor -i` to choose.",
+            diagnoses.len()
+        );
+        return;
+    };
+
+    if to_repair.is_empty() {
+        return;
+    }
+
+    match doctor::repair(&path, &to_repair) {
+        Ok(summary) => {
+            info!(
+                "Repaired {} line(s) and quarantined {}. A backup was saved to {}.",
+                summary.replaced,
+                summary.quarantined,
+                summary.backup.display()
+            );
+            if let Some(quarantine) = summary.quarantine {
+                info!("Quarantined lines were moved to {}.", quarantine.display());
+            }
+        }
+        Err(e) => {
+            error!("Unable to repair data file. {}", e);
+        }
+    }
+}
+
+/// # Template Handler
+/// Handles listing, applying and saving templates
+///
+/// ## Args
+///     - args: Clap CLI arguments of the template subcommand
+fn template(args: &ArgMatches) {
+    match args.subcommand() {
+        ("list", Some(_)) => match Template::names() {
+            Ok(names) if names.is_empty() => {
+                info!("There are no templates. Save one with `todo template save`.");
+            }
+            Ok(names) => {
+                for name in names {
+                    println!("{}", name);
+                }
+            }
+            Err(e) => {
+                error!("Couldn't read the templates. {}.", e);
+            }
+        },
+        ("apply", Some(args)) => apply_template(args),
+        ("save", Some(args)) => save_template(args),
+        _ => {
+            error!("Pass list, apply or save. See `todo template --help`.");
+        }
+    }
+}
+
+/// # Template Anchor
+/// Reads the day a template is anchored to from the anchor parameter
+///
+/// ## Args
+///     - args: Clap CLI arguments with the anchor and timezone arguments
+///     - now: the current time
+///     - config: the user's config
+///
+/// ## Rets
+///     - Option that is None if the anchor couldn't be parsed, otherwise
+///       the day or None if there is no anchor parameter
+fn template_anchor(
+    args: &ArgMatches,
+    now: DateTime<Local>,
+    config: &Config,
+) -> Option<Option<NaiveDate>> {
+    let zone = zone(args, config);
+    match args.value_of("anchor") {
+        Some(anchor) => {
+            parse_date(anchor, now, config, zone).map(|anchor| Some(zone.at(anchor).date_naive()))
+        }
+        None => Some(None),
+    }
+}
+
+/// # Apply Template
+/// Adds the items of a template to the todo list
+///
+/// ## Args
+///     - args: Clap CLI arguments with the template name
+fn apply_template(args: &ArgMatches) {
+    let now = Local::now();
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    // Clap guarantees the name is given
+    let name = args.value_of("name").unwrap();
+    let template = match Template::load(name, todo_list.scheme()) {
+        Ok(template) => template,
+        Err(e) => {
+            error!("{}.", e);
+            return;
+        }
+    };
+
+    let zone = zone(args, &config);
+    let anchor = match template_anchor(args, now, &config) {
+        Some(anchor) => anchor.unwrap_or_else(|| zone.at(now).date_naive()),
+        None => return,
+    };
+    info!("Applying {} for {}.", name, dates::format_long_day(anchor));
+
+    let ids = template.apply(&mut todo_list, anchor, zone);
+    if args.is_present("dry-run") {
+        for (index, id) in ids.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            show_item(todo_list.get(*id).unwrap(), &todo_list, zone);
+        }
+        info!("Dry run, nothing was saved.");
+        return;
+    }
+
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+    info!("Added {} todo item(s) from {}.", ids.len(), name);
+    for id in ids {
+        // The items were just added so they are in the list
+        let item = todo_list.get(id).unwrap();
+        let due = item
+            .due
+            .map(|due| {
+                format!(
+                    " (due {})",
+                    format_due(due, item.all_day, item.zone(), zone)
+                )
+            })
+            .unwrap_or_default();
+        println!("{:>4} {}{}", id, item.item, due);
+    }
+}
+
+/// # Save Template
+/// Saves the selected todo items as a template
+///
+/// ## Args
+///     - args: Clap CLI arguments with the template name and selector
+fn save_template(args: &ArgMatches) {
+    let now = Local::now();
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    // Clap guarantees the name is given
+    let name = args.value_of("name").unwrap();
+    let path = match Template::path(name) {
+        Ok(path) => path,
+        Err(e) => {
+            error!("{}.", e);
+            return;
+        }
+    };
+    if path.exists() && !args.is_present("force") {
+        error!(
+            "There is already a template named '{}'. Pass --force to replace it.",
+            name
+        );
+        return;
+    }
+
+    let ids = match select(args, &todo_list, &config, now, "Save") {
+        Some(ids) => ids,
+        None => return,
+    };
+    let items: Vec<&TodoItem> = ids.iter().filter_map(|id| todo_list.get(*id)).collect();
+
+    // Due dates are counted from the last day an item is due unless another
+    // day is given
+    let zone = zone(args, &config);
+    let anchor = match template_anchor(args, now, &config) {
+        Some(anchor) => anchor.unwrap_or_else(|| {
+            items
+                .iter()
+                .filter_map(|item| item.due)
+                .max()
+                .map_or(zone.at(now), |due| zone.at(due))
+                .date_naive()
+        }),
+        None => return,
+    };
+
+    let template = Template::from_items(&items, anchor, zone);
+    match template.save(name, todo_list.scheme()) {
+        Ok(path) => {
+            info!(
+                "Saved {} todo item(s) as {}, counting from {}. Edit it at {}.",
+                items.len(),
+                name,
+                dates::format_long_day(anchor),
+                path.display()
+            );
+        }
+        Err(e) => {
+            error!("Unable to save the template. {}.", e);
+        }
+    }
+}
+
+/// # Priorities
+/// Shows how the priorities of the todo list are written, or switches the
+/// list to another scheme. Every priority is converted to the level of the
+/// same importance in the new scheme and the list is backed up first since
+/// a scheme with fewer levels can't tell some priorities apart.
+///
+/// ## Args
+///     - args: Clap CLI arguments with the optional scheme
+fn priorities(args: &ArgMatches) {
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+    let from = todo_list.scheme();
+
+    // Clap guarantees the scheme is one of the choices
+    let to = match args.value_of("scheme") {
+        Some(scheme) => scheme.parse::<PriorityScheme>().unwrap(),
+        None => {
+            info!("Priorities are {}, {}.", from, from.levels());
+            return;
+        }
+    };
+    if to == from {
+        info!("Priorities are already {}.", from);
+        return;
+    }
+
+    let backup = match migration::backup(todo_list.path(), "priorities") {
+        Ok(backup) => backup,
+        Err(e) => {
+            error!("Unable to back up todo file. {}.", e);
+            return;
+        }
+    };
+    let converted = todo_list.set_scheme(to);
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+
+    info!(
+        "Converted {} priorities from {} to {}. A backup was saved to {}.",
+        converted,
+        from,
+        to,
+        backup.display()
+    );
+}
+
+/// # Confirm
+/// Asks the user a yes or no question on the terminal. Anything other than
+/// an answer starting with 'y' is taken as no.
+///
+/// ## Args
+///     - question: the question to ask
+///
+/// ## Rets
+///     - Whether the user answered yes
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => answer.trim().to_lowercase().starts_with('y'),
+        Err(_) => false,
+    }
+}
+
+/// # Start Item Handler
+/// Starts tracking time against a todo item, stopping whichever item was
+/// running before
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn start_item(args: &ArgMatches) {
+    // The validator guarantees the id is a valid integer
+    let id = args.value_of("id").unwrap().parse::<u32>().unwrap();
+
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    let stopped = match todo_list.start(id, Local::now()) {
+        Ok(stopped) => stopped,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+
+    if let Some(stopped) = stopped {
+        info!("Stopped working on {}.", stopped);
+    }
+    if let Some(item) = todo_list.get(id) {
+        info!("Started working on {} ({}).", id, item.item);
+    }
+}
+
+/// # Open Links Handler
+/// Opens every link of a todo item with the opener command from the config.
+/// The opener is started for each link without waiting for it to finish.
+///
+/// ## Args
+///     - args: Clap CLI arguments with the id of the item
+fn open_links(args: &ArgMatches) {
+    // The validator guarantees the id is a valid integer
+    let id = args.value_of("id").unwrap().parse::<u32>().unwrap();
+
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    let item = match todo_list.get(id) {
+        Some(item) => item,
+        None => {
+            error!("There is no todo item with id {}.", id);
+            return;
+        }
+    };
+    if item.links.is_empty() {
+        error!("Todo item {} has no links. Add some with edit --link.", id);
+        return;
+    }
+
+    // The opener can have arguments of its own before the link. The config
+    // doesn't allow an empty opener
+    let mut opener = config.opener.split_whitespace();
+    let program = opener.next().unwrap();
+    let opener_args: Vec<&str> = opener.collect();
+    for link in &item.links {
+        match Command::new(program)
+            .args(&opener_args)
+            .arg(link)
+            .stdin(Stdio::null())
+            .spawn()
+        {
+            Ok(_) => {
+                info!("Opened {}.", link);
+            }
+            Err(e) => {
+                error!("Couldn't run '{}' to open {}. {}.", config.opener, link, e);
+                return;
+            }
+        }
+    }
+}
+
+/// # Stop Item Handler
+/// Stops tracking time against the running todo item
+fn stop_item() {
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    let (id, interval) = match todo_list.stop(Local::now()) {
+        Some(stopped) => stopped,
+        None => {
+            info!("No todo item is being worked on.");
+            return;
+        }
+    };
+
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+
+    info!(
+        "Stopped working on {} after {}.",
+        id,
+        format_duration(interval.duration(Local::now()))
+    );
+}
+
+/// # Timesheet Handler
+/// Reports the time tracked per item, project and day over a range of days,
+/// either as tables or as CSV
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn timesheet(args: &ArgMatches) {
+    let now = Local::now();
+
+    // Work out the range of days to report on
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let zone = zone(args, &config);
+    let to = match args.value_of("to") {
+        Some(to) => match parse_date(to, now, &config, zone) {
+            Some(to) => zone.at(to).date_naive(),
+            None => return,
+        },
+        None => zone.at(now).date_naive(),
+    };
+    let from = match args.value_of("from") {
+        Some(from) => match parse_date(from, now, &config, zone) {
+            Some(from) => zone.at(from).date_naive(),
+            None => return,
+        },
+        None => to - Duration::days(6),
+    };
+    if from > to {
+        error!("The start of the range is after the end.");
+        return;
+    }
+
+    let todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+    let sheet = Timesheet::new(todo_list.items(), from, to, now, zone);
+
+    if args.is_present("csv") {
+        print!("{}", sheet.to_csv());
+        return;
+    }
+
+    if sheet.entries.is_empty() {
+        println!("No time was tracked between {} and {}.", from, to);
+        return;
+    }
+
+    println!("Time tracked from {} to {}", from, to);
+
+    // Time spent on each item
+    let mut table = Table::new();
+    table.add_row(row![b => "ID", "TODO", "TIME", "HOURS"]);
+    for (id, item, duration) in sheet.by_item() {
+        table.add_row(row![
+            r -> id,
+            item,
+            format_duration(duration),
+            r -> format!("{:.2}", timesheet::hours(duration))
+        ]);
+    }
+    table.printstd();
+
+    // Time spent on each project
+    let mut table = Table::new();
+    table.add_row(row![b => "PROJECT", "TIME", "HOURS"]);
+    for (project, duration) in sheet.by_project() {
+        let project = if project.is_empty() {
+            "(none)".to_string()
+        } else {
+            format!("+{}", project)
+        };
+        table.add_row(row![
+            project,
+            format_duration(duration),
+            r -> format!("{:.2}", timesheet::hours(duration))
+        ]);
+    }
+    table.printstd();
+
+    // Time spent on each day
+    let mut table = Table::new();
+    table.add_row(row![b => "DAY", "TIME", "HOURS"]);
+    for (day, duration) in sheet.by_day() {
+        table.add_row(row![
+            day.format("%a %m/%d/%y"),
+            format_duration(duration),
+            r -> format!("{:.2}", timesheet::hours(duration))
+        ]);
+    }
+    table.add_row(row![
+        b -> "TOTAL",
+        b -> format_duration(sheet.total()),
+        br -> format!("{:.2}", timesheet::hours(sheet.total()))
+    ]);
+    table.printstd();
+}
+
+/// # Workload Handler
+/// Forecasts the estimated work planned for each day and week up to the
+/// items' due dates, flagging days with more work than fits in a day
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn workload(args: &ArgMatches) {
+    // The validators guarantee both arguments parse
+    let days = args.value_of("days").unwrap().parse::<u32>().unwrap();
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let capacity = match args.value_of("capacity") {
+        Some(capacity) => workload::parse_estimate(capacity).unwrap(),
+        None => config.daily_capacity,
+    };
+
+    let todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+    let zone = zone(args, &config);
+    let today = zone.at(Local::now()).date_naive();
+    let forecast = Workload::new(todo_list.items(), today, days, capacity, zone);
+
+    // Planned work for each day, flagging those over capacity in red
+    let mut table = Table::new();
+    table.add_row(row![b => "DAY", "PLANNED", "ITEMS"]);
+    for day in &forecast.days {
+        let style_string = if day.planned > capacity { "Fr" } else { "" };
+        let items: Vec<String> = day.items.iter().map(|id| id.to_string()).collect();
+        table.add_row(Row::new(vec![
+            Cell::new(&day.date.format("%a %m/%d/%y").to_string()).style_spec(style_string),
+            Cell::new(&format_duration(day.planned)).style_spec(style_string),
+            Cell::new(&items.join(", ")),
+        ]));
+    }
+    table.printstd();
+
+    // Planned work for each week against the capacity of the forecast days
+    // that fall in that week
+    let mut table = Table::new();
+    table.add_row(row![b => "WEEK OF", "PLANNED", "CAPACITY"]);
+    for (monday, planned) in forecast.by_week() {
+        let days_in_week = forecast
+            .days
+            .iter()
+            .filter(|day| day.date >= monday && day.date < monday + Duration::days(7))
+            .count() as i32;
+        let week_capacity = capacity * days_in_week;
+        let style_string = if planned > week_capacity { "Fr" } else { "" };
+        table.add_row(Row::new(vec![
+            Cell::new(&monday.format("%m/%d/%y").to_string()).style_spec(style_string),
+            Cell::new(&format_duration(planned)).style_spec(style_string),
+            Cell::new(&format_duration(week_capacity)),
+        ]));
+    }
     table.printstd();
+
+    if forecast.unscheduled > Duration::zero() {
+        println!(
+            "{} of estimated work has no due date.",
+            format_duration(forecast.unscheduled)
+        );
+    }
+
+    let overloaded = forecast.overloaded();
+    if overloaded.is_empty() {
+        info!(
+            "No day has more than {} of work planned.",
+            format_duration(capacity)
+        );
+    } else {
+        error!(
+            "{} day(s) have more than {} of work planned.",
+            overloaded.len(),
+            format_duration(capacity)
+        );
+    }
+}
+
+/// # Selector Arguments
+/// The arguments shared by every subcommand that acts on a selection of todo
+/// items, which pick out the items and whether to ask before changing many
+/// of them
+fn selector_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("selector")
+            .help("Which todo items to act on, eg. 3, 3-5, overdue, pri:9, due:<today or +project")
+            .long_help("Which todo items to act on. Every term has to match: ids like 3, 3,5 or 3-5, 'overdue', 'pri:2' for a priority or 'pri:<2' and 'pri:>2' for lower or higher priority numbers, 'due:friday' for items due that day or 'due:<today' and 'due:>friday' for items due before or after a time, '+project' and '@context', and any other word for items containing it. Items that are done are only picked out by id.")
+            .required(true)
+            .multiple(true),
+        Arg::with_name("yes")
+            .short("y")
+            .long("yes")
+            .help("Don't ask before changing more todo items than the bulk setting of the config"),
+        color_arg(),
+        tz_arg("Read and show dates in this timezone, eg. America/New_York or UTC. Defaults to timezone from the config [OPTIONAL]"),
+    ]
+}
+
+/// # Open Config
+/// Reads the config file, alerting the user if it can't be read
+///
+/// ## Rets
+///     Option with the config, None if it couldn't be read
+fn open_config() -> Option<Config> {
+    match Config::load() {
+        Ok(config) => Some(config),
+        Err(e) => {
+            error!("Couldn't read config file. {}", e);
+            None
+        }
+    }
+}
+
+/// # Select Items
+/// Gets the ids of the todo items the selector parameter picks out. If there
+/// are more of them than the bulk setting of the config they are shown in a
+/// table and the user is asked before going on, unless the yes parameter is
+/// given.
+///
+/// ## Args
+///     args: Clap CLI arguments with the selector arguments
+///     todo_list: the todo list to select from
+///     config: the user's config
+///     now: the current time
+///     action: what will be done to the items, for the question
+///
+/// ## Rets
+///     The ids of the selected items, or None if there aren't any, an id
+///     doesn't exist or the user said no
+fn select(
+    args: &ArgMatches,
+    todo_list: &TodoList,
+    config: &Config,
+    now: DateTime<Local>,
+    action: &str,
+) -> Option<Vec<u32>> {
+    let terms: Vec<&str> = args.values_of("selector").unwrap().collect();
+    let selector = match Selector::parse(
+        &terms,
+        now,
+        config.dialect,
+        zone(args, config),
+        todo_list.scheme(),
+    ) {
+        Ok(selector) => selector,
+        Err(e) => {
+            error!("{}.", e);
+            return None;
+        }
+    };
+    let items = match todo_list.select(&selector, now) {
+        Ok(items) => items,
+        Err(e) => {
+            error!("{}.", e);
+            return None;
+        }
+    };
+    if items.is_empty() {
+        println!("No todo items match.");
+        return None;
+    }
+
+    // Show what is about to change and check before changing a lot at once
+    if items.len() > config.bulk && !args.is_present("yes") {
+        let context = render_context(args, todo_list, config, now);
+        print!("{}", Format::Table.renderer().render(&items, &context));
+        if !confirm(&format!("{} these {} todo items?", action, items.len())) {
+            return None;
+        }
+    }
+
+    Some(items.iter().map(|item| item.id).collect())
+}
+
+/// # Move Items Handler
+/// Moves the due dates of the selected todo items and prints a summary of
+/// what moved. The date is parsed with chrono_english like the due date of a
+/// new item.
+///
+/// ## Args
+///     args: Clap CLI arguments
+///     snooze: whether to move the due dates on from where they are rather
+///         than from now
+fn move_items(args: &ArgMatches, snooze: bool) {
+    let now = Local::now();
+    let when = args.value_of("when").unwrap();
+
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let action = if snooze { "Snooze" } else { "Reschedule" };
+    let ids = match select(args, &todo_list, &config, now, action) {
+        Some(ids) => ids,
+        None => return,
+    };
+
+    // Work out every new due date before changing anything so that a bad
+    // date doesn't leave some items moved and others not
+    let mut moves = Vec::new();
+    for id in ids {
+        // Select only gives back ids that are in the list
+        let item = todo_list.get(id).unwrap();
+        let base = match item.due {
+            Some(due) if snooze => due,
+            _ => now,
+        };
+
+        // Snoozed items are moved on the clock of the timezone their due
+        // date was set in so that whole days keep the same time
+        let zone = match item.tz {
+            Some(tz) if snooze => Zone::Named(tz),
+            _ => zone(args, &config),
+        };
+        let (due, all_day) = match dates::parse_due(when, base, config.dialect, zone) {
+            Ok(due) => due,
+            Err(e) => {
+                error!("{}.", e);
+                return;
+            }
+        };
+
+        // Snoozing an item that is due all day by a number of days keeps it
+        // due all day, and snoozing a timed item keeps its time
+        let (due, all_day) = match item.due {
+            Some(_) if snooze && !dates::names_time(when) && !item.all_day => {
+                (parse_date(when, base, &config, zone).unwrap(), false)
+            }
+            _ => (due, all_day),
+        };
+
+        if due < now && !args.is_present("allow-past") {
+            error!(
+                "Item {} would be due {}, which has gone by. Pass --allow-past to move it anyway.",
+                id,
+                dates::format_long(zone.at(due))
+            );
+            return;
+        }
+        moves.push((id, due, all_day, zone, item.all_day, item.zone()));
+    }
+
+    let mut moved = Vec::new();
+    for (id, due, all_day, set_in, was_all_day, was_set_in) in moves {
+        match todo_list.reschedule(id, due, all_day, set_in.named()) {
+            Ok(previous) => moved.push((
+                id,
+                previous.map(|previous| (previous, was_all_day, was_set_in)),
+                (due, all_day, set_in),
+            )),
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        }
+    }
+
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+
+    // Summarise what moved from where to where
+    info!("Moved {} todo item(s).", moved.len());
+    let zone = zone(args, &config);
+    for (id, previous, (due, all_day, set_in)) in moved {
+        let item = todo_list.get(id).unwrap();
+        println!(
+            "{:>4} {}: {} -> {}",
+            id,
+            item.item,
+            previous.map_or(
+                String::from("no due date"),
+                |(previous, all_day, set_in)| format_due(previous, all_day, set_in, zone)
+            ),
+            format_due(due, all_day, set_in, zone)
+        );
+    }
+}
+
+/// # Done Item Handler
+/// Marks the selected todo items as done
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn done_item(args: &ArgMatches) {
+    let now = Local::now();
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+    let ids = match select(args, &todo_list, &config, now, "Complete") {
+        Some(ids) => ids,
+        None => return,
+    };
+
+    for id in &ids {
+        if let Err(e) = todo_list.complete(*id, now) {
+            error!("{}", e);
+            return;
+        }
+    }
+
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+
+    for id in ids {
+        if let Some(item) = todo_list.get(id) {
+            info!("Marked {} ({}) as done.", id, item.item);
+        }
+    }
+}
+
+/// # Edit Items Handler
+/// Changes the priority, due date, wait date or estimate of the selected
+/// todo items. A value of 'none' removes the field. The validators guarantee
+/// the priority and estimate parse
+///
+/// ## Args
+///     args: Clap CLI arguments
+fn edit_items(args: &ArgMatches) {
+    let now = Local::now();
+    let config = match open_config() {
+        Some(config) => config,
+        None => return,
+    };
+
+    // The list is opened first since priorities are written in its scheme
+    let mut todo_list = match open_list() {
+        Some(todo_list) => todo_list,
+        None => return,
+    };
+
+    // Work out the changes before selecting anything so that a bad date is
+    // caught before the user is asked about it. Each change is None if the
+    // field is left alone and Some(None) if it is removed
+    let priority = match args.value_of("priority") {
+        Some("none") => Some(None),
+        Some(priority) => match parse_priority(priority, &todo_list) {
+            Some(priority) => Some(Some(priority)),
+            None => return,
+        },
+        None => None,
+    };
+    let estimate = args
+        .value_of("estimate")
+        .map(|estimate| workload::parse_estimate(estimate).ok());
+    let due = match args.value_of("due") {
+        Some("none") => Some(None),
+        Some(due) => match parse_due(args, due, now, &config) {
+            Some(due) => Some(Some(due)),
+            None => return,
+        },
+        None => None,
+    };
+    let wait = match args.value_of("wait") {
+        Some("none") => Some(None),
+        Some(wait) => match parse_date(wait, now, &config, zone(args, &config)) {
+            Some(wait) => Some(Some(wait)),
+            None => return,
+        },
+        None => None,
+    };
+    // Links are added to the ones the items have, unless they are removed
+    let links = match args.values_of("link") {
+        Some(links) => {
+            let links: Vec<&str> = links.collect();
+            if links.contains(&"none") {
+                if links.len() > 1 {
+                    error!("Links can't be added and removed at once.");
+                    return;
+                }
+                Some(None)
+            } else {
+                match resolve_links(links.into_iter()) {
+                    Some(links) => Some(Some(links)),
+                    None => return,
+                }
+            }
+        }
+        None => None,
+    };
+    if priority.is_none()
+        && estimate.is_none()
+        && due.is_none()
+        && wait.is_none()
+        && links.is_none()
+    {
+        error!("Nothing to change. Pass --priority, --due, --wait, --estimate or --link.");
+        return;
+    }
+    let ids = match select(args, &todo_list, &config, now, "Edit") {
+        Some(ids) => ids,
+        None => return,
+    };
+
+    for id in &ids {
+        // Select only gives back ids that are in the list
+        let item = todo_list.get_mut(*id).unwrap();
+        if let Some(priority) = priority {
+            item.priority = priority;
+        }
+        if let Some(due) = due {
+            item.due = due.map(|(due, _, _)| due);
+            item.all_day = due.is_some_and(|(_, all_day, _)| all_day);
+            item.tz = due.and_then(|(_, _, tz)| tz);
+        }
+        if let Some(wait) = wait {
+            item.wait = wait;
+        }
+        if let Some(estimate) = estimate {
+            item.estimate = estimate;
+        }
+        match &links {
+            Some(Some(links)) => {
+                for link in links {
+                    if !item.links.contains(link) {
+                        item.links.push(link.clone());
+                    }
+                }
+            }
+            Some(None) => item.links.clear(),
+            None => (),
+        }
+    }
+
+    if let Err(e) = todo_list.save() {
+        error!("Unable to write to todo file. {}.", e);
+        return;
+    }
+
+    info!("Edited {} todo item(s).", ids.len());
+    for id in ids {
+        if let Some(item) = todo_list.get(id) {
+            println!("{:>4} {}", id, item.item);
+        }
+    }
 }
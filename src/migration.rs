@@ -0,0 +1,240 @@
+use chrono::Local;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::priority::PriorityScheme;
+
+/// Version of the data file format written by this library
+pub const CURRENT_VERSION: u32 = 4;
+
+/// Text that starts the version header on the first line of the data file
+const HEADER_PREFIX: &str = "# todo-format: ";
+
+/// Key of the header attribute that records the priority scheme of the list
+const SCHEME_KEY: &str = "priorities";
+
+/// # Upgrade Record
+/// Describes an upgrade that was applied to a data file when it was opened.
+///
+/// ## Data Members
+///   - from: version the file was in before the upgrade
+///   - to: version the file is in now
+///   - backup: copy of the file as it was before the upgrade
+#[derive(Clone, Debug)]
+pub struct Upgrade {
+    pub from: u32,
+    pub to: u32,
+    pub backup: PathBuf,
+}
+
+/// # Migration
+/// A single step that rewrites the item lines of a data file from one
+/// version to the next. Migrations must never depend on the current
+/// `TodoItem` serialization since that will keep changing after they are
+/// written.
+struct Migration {
+    from: u32,
+    migrate: fn(Vec<String>) -> Result<Vec<String>>,
+}
+
+/// Every migration in order. Each one upgrades by exactly one version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        migrate: v1_to_v2,
+    },
+    Migration {
+        from: 2,
+        migrate: v2_to_v3,
+    },
+    Migration {
+        from: 3,
+        migrate: v3_to_v4,
+    },
+];
+
+/// # Header
+/// Gets the version header line for the current format. Lists that don't
+/// use numeric priorities record their scheme as a tab separated attribute
+/// after the version, like the attributes of an item:
+/// ```text
+/// # todo-format: 4\tpriorities=letters
+/// ```
+///
+/// ## Args
+///   - scheme: how the priorities of the list are written
+pub fn header(scheme: PriorityScheme) -> String {
+    if scheme == PriorityScheme::default() {
+        format!("{}{}", HEADER_PREFIX, CURRENT_VERSION)
+    } else {
+        format!(
+            "{}{}\t{}={}",
+            HEADER_PREFIX, CURRENT_VERSION, SCHEME_KEY, scheme
+        )
+    }
+}
+
+/// # Is Header
+/// Whether a line of a data file is the version header
+pub fn is_header(line: &str) -> bool {
+    line.starts_with(HEADER_PREFIX)
+}
+
+/// # Detect Version
+/// Reads the format version from the first line of a data file. Files
+/// written before versioning existed have no header and are version 1.
+///
+/// ## Args
+///   - first_line: the first line of the data file, if there is one
+///
+/// ## Rets
+///   - Result with the version or an error if the header is malformed
+pub fn detect_version(first_line: Option<&str>) -> Result<u32> {
+    match first_line {
+        Some(line) if is_header(line) => line[HEADER_PREFIX.len()..]
+            .split('\t')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| Error::Parse(format!("Invalid format version header: {}", line))),
+        _ => Ok(1),
+    }
+}
+
+/// # Detect Priority Scheme
+/// Reads the priority scheme from the header line of a data file. Files
+/// that don't record one use numeric priorities.
+///
+/// ## Args
+///   - first_line: the first line of the data file, if there is one
+///
+/// ## Rets
+///   - Result with the scheme or an error if the recorded one is unknown
+pub fn detect_scheme(first_line: Option<&str>) -> Result<PriorityScheme> {
+    let attributes = match first_line {
+        Some(line) if is_header(line) => line.split('\t').skip(1),
+        _ => return Ok(PriorityScheme::default()),
+    };
+
+    for attribute in attributes {
+        if let Some(scheme) = attribute
+            .strip_prefix(SCHEME_KEY)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return scheme.parse::<PriorityScheme>();
+        }
+    }
+    Ok(PriorityScheme::default())
+}
+
+/// # Migrate
+/// Upgrades the item lines of a data file to the current version by
+/// running every migration after the given version in order.
+///
+/// ## Args
+///   - lines: the item lines, without any version header
+///   - version: the version that the lines are written in
+///
+/// ## Rets
+///   - Result with the upgraded lines or an error
+pub fn migrate(mut lines: Vec<String>, version: u32) -> Result<Vec<String>> {
+    if version > CURRENT_VERSION {
+        return Err(Error::UnsupportedVersion {
+            found: version,
+            supported: CURRENT_VERSION,
+        });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= version) {
+        lines = (migration.migrate)(lines)?;
+    }
+
+    Ok(lines)
+}
+
+/// # Backup
+/// Copies the data file next to itself before it is changed in bulk. The
+/// backup is named after the given tag and the time it was taken so that
+/// earlier backups are never overwritten.
+///
+/// ## Args
+///   - path: location of the data file
+///   - tag: short label for why the backup was taken (eg. "v1")
+///
+/// ## Rets
+///   - Result with the location of the backup
+pub fn backup(path: &Path, tag: &str) -> Result<PathBuf> {
+    let stamp = Local::now().format("%Y%m%d%H%M%S").to_string();
+
+    // Two backups taken within the same second are told apart by a counter
+    let mut attempt = 1;
+    let backup = loop {
+        let mut backup = path.to_path_buf().into_os_string();
+        if attempt == 1 {
+            backup.push(format!(".{}-{}.bak", tag, stamp));
+        } else {
+            backup.push(format!(".{}-{}-{}.bak", tag, stamp, attempt));
+        }
+
+        let backup = PathBuf::from(backup);
+        if !backup.exists() {
+            break backup;
+        }
+        attempt += 1;
+    };
+
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// # Version 1 to 2
+/// Version 1 is the original unversioned `[PRIORITY]{DATE} TEXT` format.
+/// Version 2 adds tab separated `key=value` attributes after the text and
+/// gives every item an id. Lines that can't be read are kept as they are.
+fn v1_to_v2(lines: Vec<String>) -> Result<Vec<String>> {
+    lazy_static! {
+        static ref V1_RE: Regex = Regex::new(
+            r"^\[([0-9]?)\]\{(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)? [+-]\d{2}:\d{2})?\} (.*)$",
+        )
+        .unwrap();
+    }
+
+    let mut id = 0;
+    Ok(lines
+        .into_iter()
+        .map(|line| match V1_RE.captures(&line) {
+            Some(cap) => {
+                id += 1;
+                format!(
+                    "[{}]{{{}}} {}\tid={}",
+                    &cap[1],
+                    cap.get(2).map_or("", |due| due.as_str()),
+                    cap[3].replace('\t', " "),
+                    id
+                )
+            }
+            None => line,
+        })
+        .collect())
+}
+
+/// # Version 2 to 3
+/// Version 3 allows a due date to be a day on its own (`{2020-04-05}`) for
+/// items that are due all day. Every version 2 line is already a valid
+/// version 3 line, so the lines are kept as they are and only the header
+/// changes, which stops older versions from misreading all day items.
+fn v2_to_v3(lines: Vec<String>) -> Result<Vec<String>> {
+    Ok(lines)
+}
+
+/// # Version 3 to 4
+/// Version 4 records the priority scheme of the list in the header and
+/// allows priorities of two digits for schemes with more than ten levels.
+/// Version 3 lists always used numeric priorities, which is what a header
+/// without a scheme means, so the lines are kept as they are.
+fn v3_to_v4(lines: Vec<String>) -> Result<Vec<String>> {
+    Ok(lines)
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// Names of the levels of the named scheme, most important first
+const NAMES: &[&str] = &["critical", "high", "medium", "low"];
+
+/// # Priority Scheme
+/// How the priorities of a list are written. Items always store their
+/// priority as a rank from 0, the most important, up to the lowest rank of
+/// the scheme, so the scheme only changes how ranks are typed and shown.
+///
+///   - Numeric: 0 to 9
+///   - Letters: A to Z, like todo.txt
+///   - Named: critical, high, medium and low
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PriorityScheme {
+    #[default]
+    Numeric,
+    Letters,
+    Named,
+}
+
+// FromStr trait implementation so the scheme can be given on the command
+// line and read from the data file
+impl FromStr for PriorityScheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<PriorityScheme, Error> {
+        match s.to_lowercase().as_str() {
+            "numeric" | "numbers" => Ok(PriorityScheme::Numeric),
+            "letters" => Ok(PriorityScheme::Letters),
+            "named" | "names" => Ok(PriorityScheme::Named),
+            _ => Err(Error::Parse(format!(
+                "Unknown priority scheme '{}'. Expected numeric, letters or named",
+                s
+            ))),
+        }
+    }
+}
+
+// Display trait implementation that writes the scheme name back out
+impl fmt::Display for PriorityScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityScheme::Numeric => write!(f, "numeric"),
+            PriorityScheme::Letters => write!(f, "letters"),
+            PriorityScheme::Named => write!(f, "named"),
+        }
+    }
+}
+
+impl PriorityScheme {
+    /// # Lowest Rank
+    /// Gets the rank of the least important priority of the scheme
+    pub fn lowest(self) -> i8 {
+        match self {
+            PriorityScheme::Numeric => 9,
+            PriorityScheme::Letters => 25,
+            PriorityScheme::Named => NAMES.len() as i8 - 1,
+        }
+    }
+
+    /// # Levels
+    /// Describes the priorities of the scheme, for help and error messages
+    pub fn levels(self) -> &'static str {
+        match self {
+            PriorityScheme::Numeric => "a number from 0 to 9",
+            PriorityScheme::Letters => "a letter from A to Z",
+            PriorityScheme::Named => "critical, high, medium or low",
+        }
+    }
+
+    /// # Parse Priority
+    /// Reads a priority written in the scheme. Letters and names can be
+    /// given in any case, and names can be shortened to their first letter.
+    ///
+    /// ## Args
+    ///   - text: the priority as it was typed
+    ///
+    /// ## Rets
+    ///   - Result with the rank of the priority or an error if it isn't
+    ///     part of the scheme
+    pub fn parse(self, text: &str) -> Result<i8, Error> {
+        let text = text.trim();
+        let rank = match self {
+            PriorityScheme::Numeric => text.parse::<i8>().ok(),
+            PriorityScheme::Letters => match text.as_bytes() {
+                [letter] if letter.is_ascii_alphabetic() => {
+                    Some((letter.to_ascii_uppercase() - b'A') as i8)
+                }
+                _ => None,
+            },
+            PriorityScheme::Named => NAMES
+                .iter()
+                .position(|name| {
+                    name.eq_ignore_ascii_case(text)
+                        || (text.len() == 1 && name[..1].eq_ignore_ascii_case(text))
+                })
+                .map(|rank| rank as i8),
+        };
+
+        rank.filter(|rank| (0..=self.lowest()).contains(rank))
+            .ok_or_else(|| {
+                Error::Parse(format!(
+                    "Invalid priority '{}'. Expected {}",
+                    text,
+                    self.levels()
+                ))
+            })
+    }
+
+    /// # Label
+    /// Writes a rank the way the scheme shows it. Ranks outside of the
+    /// scheme can only come from a hand edited file so they are shown as
+    /// numbers.
+    pub fn label(self, rank: i8) -> String {
+        if !(0..=self.lowest()).contains(&rank) {
+            return rank.to_string();
+        }
+        match self {
+            PriorityScheme::Numeric => rank.to_string(),
+            PriorityScheme::Letters => ((b'A' + rank as u8) as char).to_string(),
+            PriorityScheme::Named => NAMES[rank as usize].to_string(),
+        }
+    }
+
+    /// # Importance
+    /// Gets how important a rank is on a scale from 1 for the most important
+    /// priority down to 0 for the least important, so that schemes with
+    /// different numbers of levels can be colored and scored alike
+    pub fn importance(self, rank: i8) -> f64 {
+        let lowest = self.lowest();
+        f64::from(lowest - rank.clamp(0, lowest)) / f64::from(lowest)
+    }
+
+    /// # Convert
+    /// Moves a rank to the rank of the same importance in another scheme,
+    /// rounding to the nearest level
+    ///
+    /// ## Args
+    ///   - rank: the rank in this scheme
+    ///   - to: the scheme to convert it to
+    pub fn convert(self, rank: i8, to: PriorityScheme) -> i8 {
+        let rank = rank.clamp(0, self.lowest());
+        (f64::from(rank) * f64::from(to.lowest()) / f64::from(self.lowest())).round() as i8
+    }
+}
@@ -0,0 +1,203 @@
+use chrono::{DateTime, Local};
+use std::ops::Range;
+
+use crate::dates::{self, DateDialect};
+use crate::error::{Error, Result};
+use crate::link;
+use crate::priority::PriorityScheme;
+use crate::zone::Zone;
+
+/// Most words a due date written in the text can take up, eg. "next friday
+/// at 3pm"
+const MAX_DATE_WORDS: usize = 4;
+
+/// Words that can lead into a due date without being part of it
+const DATE_LEADS: &[&str] = &["at", "on", "by", "in", "due", "before"];
+
+/// Words that mark the due date when it isn't at the end of the text, eg.
+/// "Call Sam due: friday about the report". "due:" can also be written
+/// against the date, like "due:friday"
+const DATE_MARKERS: &[&str] = &["due:", "@"];
+
+/// Words without any digits that can be part of a due date. The date parser
+/// skips over words it doesn't know, so runs with any other words in them
+/// are never tried
+const DATE_WORDS: &str = "today tomorrow tonight next this at a an am pm \
+    min mins minute minutes h hr hrs hour hours day days week weeks month months year years \
+    monday mon tuesday tue tues wednesday wed thursday thu thur thurs friday fri \
+    saturday sat sunday sun january february march april may june \
+    july august september october november december";
+
+/// Short names of months, which are only part of a due date along with a
+/// day number, like "mar 5", since on their own they are often just words
+const MONTH_ABBREVIATIONS: &str = "jan feb mar apr jun jul aug sep sept oct nov dec";
+
+/// # Quick Add
+/// A todo item typed as a single line, split into its parts. Projects and
+/// contexts are part of the text so they are kept in it.
+///
+/// ## Data Members
+///   - text: the todo text with the priority and due date taken out
+///   - priority: the rank of the priority, written as `!2`, `!B` or `!high`
+///   - due: the words of the due date, to be parsed like a due date given
+///     on its own
+///   - links: URLs written in the text, like `https://example.com/pr/12`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuickAdd {
+    pub text: String,
+    pub priority: Option<i8>,
+    pub due: Option<String>,
+    pub links: Vec<String>,
+}
+
+impl QuickAdd {
+    /// # Parse Quick Add
+    /// Splits a line like `Call dentist tomorrow 3pm !2 +health @phone`
+    /// into its parts. A word starting with '!' that is a priority of the
+    /// scheme is the priority. The longest run of words that reads as a date
+    /// that hasn't gone by is the due date, the last one if there are
+    /// several, along with a word like "at" or "by" just before it. The date
+    /// has to be at the end of the text, with only projects and contexts
+    /// after it, or come after a marker like "due:". Words that are URLs are
+    /// taken out as links.
+    ///
+    /// ## Args
+    ///   - line: the line as it was typed
+    ///   - now: the current time, for dates like "tomorrow"
+    ///   - dialect: how dates written as numbers are read
+    ///   - zone: the timezone dates are typed in
+    ///   - scheme: how the priorities of the list are written
+    ///
+    /// ## Rets
+    ///   - Result with the parts, or an error if there is no text left
+    pub fn parse(
+        line: &str,
+        now: DateTime<Local>,
+        dialect: DateDialect,
+        zone: Zone,
+        scheme: PriorityScheme,
+    ) -> Result<QuickAdd> {
+        let mut quick_add = QuickAdd::default();
+
+        // A marker written against the date, like "due:friday", is split off
+        let mut words: Vec<&str> = Vec::new();
+        for word in line.split_whitespace() {
+            match word.get(..4) {
+                Some(marker) if marker.eq_ignore_ascii_case("due:") && word.len() > 4 => {
+                    words.push(marker);
+                    words.push(&word[4..]);
+                }
+                _ => words.push(word),
+            }
+        }
+
+        // URLs are taken out first so their digits aren't read as dates
+        words.retain(|word| {
+            if link::is_url(word) {
+                quick_add.links.push(word.to_string());
+                false
+            } else {
+                true
+            }
+        });
+
+        // Only the last priority counts, the way a later flag would win
+        if let Some(index) = words.iter().rposition(|word| {
+            word.strip_prefix('!')
+                .is_some_and(|priority| scheme.parse(priority).is_ok())
+        }) {
+            quick_add.priority = scheme.parse(&words.remove(index)[1..]).ok();
+        }
+
+        if let Some(dates) = find_date(&words, now, dialect, zone) {
+            quick_add.due = Some(date_text(&words[dates.clone()]));
+
+            // Take out a word leading into the date as well
+            let start = match dates.start.checked_sub(1) {
+                Some(lead) if is_lead(words[lead]) => lead,
+                _ => dates.start,
+            };
+            words.drain(start..dates.end);
+        }
+
+        quick_add.text = words.join(" ");
+        if quick_add.text.is_empty() {
+            return Err(Error::Parse(format!("There is no todo text in '{}'", line)));
+        }
+        Ok(quick_add)
+    }
+}
+
+/// # Find Date
+/// Finds the longest run of words that reads as a date that hasn't gone by,
+/// preferring the last one when runs are the same length. Runs are made up
+/// of date words and words with digits in them, but a lone number would
+/// read as a time so it is never a date on its own. A run has to be at the
+/// end of the text, apart from projects and contexts, or come right after a
+/// date marker, so that words like "sun" in the middle of the text are left
+/// alone.
+fn find_date(
+    words: &[&str],
+    now: DateTime<Local>,
+    dialect: DateDialect,
+    zone: Zone,
+) -> Option<Range<usize>> {
+    let is_tag = |word: &&str| word.len() > 1 && (word.starts_with('+') || word.starts_with('@'));
+    let has_digit = |word: &&str| word.chars().any(|c| c.is_ascii_digit());
+    let in_list = |list: &str, word: &str| {
+        let word = word.to_lowercase();
+        list.split_whitespace().any(|listed| listed == word)
+    };
+    let usable = |word: &&str| {
+        !is_tag(word)
+            && !word.starts_with('!')
+            && (has_digit(word) || in_list(DATE_WORDS, word) || in_list(MONTH_ABBREVIATIONS, word))
+    };
+
+    for length in (1..=MAX_DATE_WORDS.min(words.len())).rev() {
+        for start in (0..=words.len() - length).rev() {
+            let run = &words[start..start + length];
+            let placed = words[start + length..].iter().all(is_tag)
+                || start
+                    .checked_sub(1)
+                    .is_some_and(|marker| is_marker(words[marker]));
+            if !placed
+                || !run.iter().all(usable)
+                || run
+                    .iter()
+                    .all(|word| word.chars().all(|c| c.is_ascii_digit()))
+                || (run.iter().any(|word| in_list(MONTH_ABBREVIATIONS, word))
+                    && !run.iter().any(has_digit))
+            {
+                continue;
+            }
+            match dates::parse_due(&date_text(run), now, dialect, zone) {
+                Ok((due, _)) if due >= now => return Some(start..start + length),
+                _ => (),
+            }
+        }
+    }
+    None
+}
+
+/// Whether a word marks the due date that comes after it
+fn is_marker(word: &str) -> bool {
+    DATE_MARKERS.contains(&word.to_lowercase().as_str())
+}
+
+/// Whether a word leads into the due date that comes after it and is taken
+/// out along with it
+fn is_lead(word: &str) -> bool {
+    is_marker(word) || DATE_LEADS.contains(&word.to_lowercase().as_str())
+}
+
+/// Joins a run of words into the text of a date. The date parser doesn't
+/// understand "at" between a day and a time, like "monday at 9am", so it is
+/// left out.
+fn date_text(run: &[&str]) -> String {
+    run.iter()
+        .filter(|word| !word.eq_ignore_ascii_case("at"))
+        .copied()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
@@ -0,0 +1,674 @@
+use chrono::{DateTime, Local};
+use prettytable::{format, Cell, Row, Table};
+use std::env;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use termion::{color, style};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::dates::{DateDisplay, DateStyle, DueBand};
+use crate::error::Error;
+use crate::link;
+use crate::priority::PriorityScheme;
+use crate::time_tracking::format_duration;
+use crate::todo_item::TodoItem;
+use crate::urgency::Scorer;
+use crate::zone::Zone;
+
+/// Gradient for the priorities. This goes from red for the most important
+/// priority to green for the least important
+const PRIORITY_COLORS: [color::Rgb; 10] = [
+    color::Rgb(180, 19, 19),
+    color::Rgb(162, 31, 22),
+    color::Rgb(144, 44, 26),
+    color::Rgb(126, 57, 30),
+    color::Rgb(108, 70, 34),
+    color::Rgb(91, 83, 38),
+    color::Rgb(73, 96, 42),
+    color::Rgb(55, 109, 46),
+    color::Rgb(37, 122, 50),
+    color::Rgb(20, 135, 54),
+];
+
+/// Link labels are laid out in tables between these marks, which take up no
+/// room, and swapped for hyperlinks once the table is drawn since the table
+/// can't measure the width of hyperlink escapes
+const LABEL_START: char = '\u{2}';
+const LABEL_END: char = '\u{3}';
+
+/// # Output Format
+/// The ways a list of todo items can be printed.
+///
+///   - Table: a bordered table of the usual columns
+///   - Plain: one tab separated line per item without a header or colors,
+///     for grepping and piping into other tools
+///   - Compact: the usual columns without borders
+///   - Wide: a bordered table of every field
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Table,
+    Plain,
+    Compact,
+    Wide,
+}
+
+/// Names of the output formats, for help and error messages
+pub const FORMATS: &str = "table, plain, compact, wide";
+
+// FromStr trait implementation so the format can be named on the command line
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Format, Error> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "plain" => Ok(Format::Plain),
+            "compact" => Ok(Format::Compact),
+            "wide" => Ok(Format::Wide),
+            _ => Err(Error::Parse(format!(
+                "Unknown format '{}'. Expected one of {}",
+                s, FORMATS
+            ))),
+        }
+    }
+}
+
+// Display trait implementation that writes the format name back out
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl Format {
+    /// # Renderer
+    /// Gets the renderer that prints items in this format
+    pub fn renderer(self) -> Box<dyn Renderer> {
+        match self {
+            Format::Table => Box::new(TableRenderer {
+                columns: Column::DEFAULT,
+                borders: true,
+            }),
+            Format::Compact => Box::new(TableRenderer {
+                columns: Column::DEFAULT,
+                borders: false,
+            }),
+            Format::Wide => Box::new(TableRenderer {
+                columns: Column::ALL,
+                borders: true,
+            }),
+            Format::Plain => Box::new(PlainRenderer),
+        }
+    }
+}
+
+/// # Color Choice
+/// Whether output is colored. Auto colors output going to a terminal unless
+/// the NO_COLOR environment variable is set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    Always,
+    Never,
+    Auto,
+}
+
+// FromStr trait implementation so the choice can be given on the command line
+impl FromStr for ColorChoice {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ColorChoice, Error> {
+        match s.to_lowercase().as_str() {
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            "auto" => Ok(ColorChoice::Auto),
+            _ => Err(Error::Parse(format!(
+                "Unknown color choice '{}'. Expected one of always, never, auto",
+                s
+            ))),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// # Enabled
+    /// Works out whether to color output written to stdout
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                // See https://no-color.org, an empty value doesn't count
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && termion::is_tty(&io::stdout())
+            }
+        }
+    }
+}
+
+/// # Terminal Width
+/// Gets the width of the terminal stdout is printed to, or None if it isn't
+/// going to a terminal
+pub fn terminal_width() -> Option<usize> {
+    if !termion::is_tty(&io::stdout()) {
+        return None;
+    }
+    termion::terminal_size()
+        .ok()
+        .map(|(width, _)| usize::from(width))
+}
+
+/// # Terminal Height
+/// Gets how many lines fit in the terminal stdout is printed to, or None if
+/// it isn't going to a terminal
+pub fn terminal_height() -> Option<usize> {
+    if !termion::is_tty(&io::stdout()) {
+        return None;
+    }
+    termion::terminal_size()
+        .ok()
+        .map(|(_, height)| usize::from(height))
+}
+
+/// # Render Context
+/// What a renderer needs to know besides the items themselves.
+///
+/// ## Data Members
+///   - now: the current time, for overdue dates and running timers
+///   - scorer: urgency scorer for the whole list
+///   - color: whether to write color, style and hyperlink escapes
+///   - width: how wide the output may be. Tables fit the text column into
+///     it, and None leaves them as wide as they need to be
+///   - truncate: cut text that doesn't fit short instead of wrapping it
+///   - dates: the style each date column is shown in
+///   - zone: the timezone dates are shown in
+///   - scheme: how the priorities of the list are written
+pub struct Context<'a> {
+    pub now: DateTime<Local>,
+    pub scorer: Scorer<'a>,
+    pub color: bool,
+    pub width: Option<usize>,
+    pub truncate: bool,
+    pub dates: DateDisplay,
+    pub zone: Zone,
+    pub scheme: PriorityScheme,
+}
+
+impl<'a> Context<'a> {
+    /// # Format a Date
+    /// Formats a date that isn't a due date in a style, in the zone dates
+    /// are shown in
+    pub fn format(&self, style: DateStyle, date: DateTime<Local>) -> String {
+        style.format(self.zone.at(date), self.zone.at(self.now), false)
+    }
+
+    /// # Paint
+    /// Wraps text in a style and its reset when color is enabled
+    ///
+    /// ## Args
+    ///   - text: the text to style
+    ///   - start: escape that turns the style on
+    ///   - end: escape that turns the style off
+    pub fn paint(&self, text: &str, start: &dyn fmt::Display, end: &dyn fmt::Display) -> String {
+        if self.color {
+            format!("{}{}{}", start, text, end)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// # Paint Priority
+    /// Colors text on a gradient from red for the most important priority
+    /// of the scheme to green for the least important. Schemes with more or
+    /// fewer levels than the gradient are spread evenly across it.
+    /// Priorities outside of the scheme can only come from a hand edited
+    /// file so they are left uncolored.
+    pub fn paint_priority(&self, text: &str, priority: i8) -> String {
+        if !(0..=self.scheme.lowest()).contains(&priority) {
+            return text.to_string();
+        }
+        let last = PRIORITY_COLORS.len() - 1;
+        let shade = ((1.0 - self.scheme.importance(priority)) * last as f64).round() as usize;
+        self.paint(
+            text,
+            &color::Fg(PRIORITY_COLORS[shade]),
+            &color::Fg(color::Reset),
+        )
+    }
+
+    /// # Paint Band
+    /// Colors text by how soon something is due: overdue in bright red,
+    /// today in yellow and in the next week in cyan
+    pub fn paint_band(&self, text: &str, band: DueBand) -> String {
+        let reset = color::Fg(color::Reset);
+        match band {
+            DueBand::Overdue => self.paint(text, &color::Fg(color::LightRed), &reset),
+            DueBand::Today => self.paint(text, &color::Fg(color::Yellow), &reset),
+            DueBand::Tomorrow | DueBand::ThisWeek => {
+                self.paint(text, &color::Fg(color::Cyan), &reset)
+            }
+            DueBand::Later => text.to_string(),
+        }
+    }
+}
+
+/// # Renderer
+/// Turns a list of todo items into text to print.
+pub trait Renderer {
+    /// # Render
+    /// Renders the items in the order given
+    ///
+    /// ## Args
+    ///   - items: the todo items to render
+    ///   - context: the current time and urgency scores
+    ///
+    /// ## Rets
+    ///   - The rendered text, ending in a newline
+    fn render(&self, items: &[&TodoItem], context: &Context) -> String;
+}
+
+/// # Column
+/// A field of a todo item shown in a table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Id,
+    Priority,
+    Due,
+    Text,
+    Links,
+    Created,
+    Completed,
+    Wait,
+    Estimate,
+    Spent,
+    Depends,
+    Urgency,
+}
+
+impl Column {
+    /// The columns shown by the table and compact formats
+    pub const DEFAULT: &'static [Column] = &[
+        Column::Id,
+        Column::Priority,
+        Column::Due,
+        Column::Text,
+        Column::Links,
+        Column::Estimate,
+        Column::Spent,
+        Column::Urgency,
+    ];
+
+    /// Every column, shown by the wide format
+    pub const ALL: &'static [Column] = &[
+        Column::Id,
+        Column::Priority,
+        Column::Due,
+        Column::Text,
+        Column::Links,
+        Column::Created,
+        Column::Completed,
+        Column::Wait,
+        Column::Estimate,
+        Column::Spent,
+        Column::Depends,
+        Column::Urgency,
+    ];
+
+    /// The heading of the column
+    pub fn heading(self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Priority => "PRIORITY",
+            Column::Due => "DUE DATE",
+            Column::Text => "TODO",
+            Column::Links => "LINKS",
+            Column::Created => "CREATED",
+            Column::Completed => "COMPLETED",
+            Column::Wait => "WAIT",
+            Column::Estimate => "ESTIMATE",
+            Column::Spent => "TIME",
+            Column::Depends => "DEPENDS",
+            Column::Urgency => "URGENCY",
+        }
+    }
+
+    /// Whether the column is right aligned, centered or left aligned
+    fn align(self) -> &'static str {
+        match self {
+            Column::Id | Column::Urgency => "r",
+            Column::Priority => "c",
+            _ => "l",
+        }
+    }
+
+    /// # Cell Text
+    /// Gets the text of this column for an item, with any styling. The todo
+    /// text is styled by the table once it has been fit to the width.
+    fn text(self, item: &TodoItem, context: &Context) -> String {
+        match self {
+            Column::Id => item.id.to_string(),
+            // Pretty print the priority
+            Column::Priority => match item.priority {
+                Some(priority) => context.paint(
+                    &context.paint_priority(&context.scheme.label(priority), priority),
+                    &style::Bold,
+                    &style::Reset,
+                ),
+                None => String::new(),
+            },
+            // Due dates of items that aren't done are colored by how soon
+            // they are. Items due all day are shown without a time, on the
+            // day they are due wherever they are shown
+            Column::Due => match item.due {
+                Some(due) => {
+                    let style = context.dates.due;
+                    let now = context.zone.at(context.now);
+                    let text = if item.all_day {
+                        let day = item.zone().at(due).date_naive();
+                        style.format_day(day, now.date_naive(), !item.is_done())
+                    } else {
+                        style.format(context.zone.at(due), now, !item.is_done())
+                    };
+                    if item.is_done() {
+                        text
+                    } else {
+                        let zone = item.due_zone(context.zone);
+                        context.paint_band(&text, DueBand::of(due, context.now, zone))
+                    }
+                }
+                None => String::new(),
+            },
+            Column::Text => item.item.clone(),
+            // Links are shown by a short label that is a hyperlink when
+            // escapes are written, and in full when they aren't
+            Column::Links if context.color => {
+                let labels: Vec<String> = item
+                    .links
+                    .iter()
+                    .map(|link| format!("{}{}{}", LABEL_START, link::label(link), LABEL_END))
+                    .collect();
+                labels.join(" ")
+            }
+            Column::Links => item.links.join(" "),
+            Column::Created => item
+                .created
+                .map(|created| context.format(context.dates.created, created))
+                .unwrap_or_default(),
+            Column::Completed => item
+                .completed
+                .map(|completed| context.format(context.dates.completed, completed))
+                .unwrap_or_default(),
+            Column::Wait => item
+                .wait
+                .map(|wait| context.format(context.dates.wait, wait))
+                .unwrap_or_default(),
+            Column::Estimate => item.estimate.map(format_duration).unwrap_or_default(),
+            // The running item is bolded
+            Column::Spent if item.time.is_empty() => String::new(),
+            Column::Spent if item.running_interval().is_some() => context.paint(
+                &format_duration(item.time_spent(context.now)),
+                &style::Bold,
+                &style::Reset,
+            ),
+            Column::Spent => format_duration(item.time_spent(context.now)),
+            Column::Depends => {
+                let depends: Vec<String> = item.depends.iter().map(u32::to_string).collect();
+                depends.join(",")
+            }
+            // Done items aren't urgent
+            Column::Urgency if item.is_done() => String::new(),
+            Column::Urgency => format!("{:.1}", context.scorer.score(item)),
+        }
+    }
+}
+
+/// # Table Renderer
+/// Renders items as a table with one row per item.
+///
+/// ## Data Members
+///   - columns: the columns to show, in order
+///   - borders: whether to draw lines around the cells
+pub struct TableRenderer {
+    pub columns: &'static [Column],
+    pub borders: bool,
+}
+
+impl Renderer for TableRenderer {
+    fn render(&self, items: &[&TodoItem], context: &Context) -> String {
+        let mut table = Table::new();
+        if !self.borders {
+            table.set_format(*format::consts::FORMAT_CLEAN);
+        }
+
+        // Work out the text of every cell up front so that the width left
+        // over for the todo text is known
+        let headings: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| context.paint(column.heading(), &style::Bold, &style::Reset))
+            .collect();
+        let rows: Vec<Vec<String>> = items
+            .iter()
+            .map(|item| {
+                self.columns
+                    .iter()
+                    .map(|column| column.text(item, context))
+                    .collect()
+            })
+            .collect();
+        let text_width = context
+            .width
+            .and_then(|width| self.text_width(width, &headings, &rows));
+
+        table.set_titles(Row::new(
+            headings.iter().map(|heading| Cell::new(heading)).collect(),
+        ));
+
+        for (item, row) in items.iter().zip(rows) {
+            let cells = self
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(column, text)| {
+                    if *column != Column::Text {
+                        return Cell::new(&text).style_spec(column.align());
+                    }
+
+                    // Fit the todo text into the space left, styling each
+                    // line on its own so the style doesn't run into the
+                    // borders. Done items are shown in italics
+                    let lines = match text_width {
+                        Some(width) if context.truncate => vec![truncate(&text, width)],
+                        Some(width) => wrap(&text, width),
+                        None => vec![text],
+                    };
+                    let lines: Vec<String> = lines
+                        .iter()
+                        .map(|line| {
+                            if item.is_done() {
+                                context.paint(line, &style::Italic, &style::Reset)
+                            } else {
+                                line.clone()
+                            }
+                        })
+                        .collect();
+                    Cell::new(&lines.join("\n"))
+                })
+                .collect();
+            table.add_row(Row::new(cells));
+        }
+
+        // Rows are drawn in the order of the items, so the labels come out
+        // in the order of their links
+        hyperlink_labels(
+            &table.to_string(),
+            items.iter().flat_map(|item| item.links.iter()),
+        )
+    }
+}
+
+/// # Hyperlink Labels
+/// Swaps each link label laid out in a drawn table for a hyperlink to the
+/// next link
+///
+/// ## Args
+///   - table: the drawn table
+///   - links: the links of the labels, in the order they appear
+fn hyperlink_labels<'a>(table: &str, mut links: impl Iterator<Item = &'a String>) -> String {
+    let mut linked = String::with_capacity(table.len());
+    let mut rest = table;
+    while let Some(start) = rest.find(LABEL_START) {
+        let end = match rest[start..].find(LABEL_END) {
+            Some(end) => start + end,
+            None => break,
+        };
+        linked.push_str(&rest[..start]);
+
+        // The marks are a single byte each
+        let label = &rest[start + 1..end];
+        match links.next() {
+            Some(link) => linked.push_str(&link::hyperlink(link, label)),
+            None => linked.push_str(label),
+        }
+        rest = &rest[end + 1..];
+    }
+    linked.push_str(rest);
+    linked
+}
+
+impl TableRenderer {
+    /// # Text Width
+    /// Works out how wide the todo text column can be for the whole table
+    /// to fit in the given width. None if the text already fits.
+    fn text_width(&self, width: usize, headings: &[String], rows: &[Vec<String>]) -> Option<usize> {
+        // The narrowest the text column will be squeezed to, however little
+        // room the other columns leave
+        const MIN_TEXT_WIDTH: usize = 10;
+
+        // Each column is padded by a space on either side, and bordered
+        // tables have a line before every column and one at the end
+        let padding = if self.borders { 3 } else { 2 };
+        let mut used = if self.borders { 1 } else { 0 };
+        let mut text = 0;
+        for (index, column) in self.columns.iter().enumerate() {
+            let widest = rows
+                .iter()
+                .map(|row| display_width(&row[index]))
+                .chain(std::iter::once(display_width(&headings[index])))
+                .max()
+                .unwrap_or(0);
+            if *column == Column::Text {
+                text = widest;
+            } else {
+                used += widest;
+            }
+            used += padding;
+        }
+
+        if used + text <= width {
+            return None;
+        }
+        Some(width.saturating_sub(used).max(MIN_TEXT_WIDTH))
+    }
+}
+
+/// # Display Width
+/// Gets how many columns text takes up in a terminal, skipping escapes
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        if in_escape {
+            in_escape = c != 'm';
+        } else if c == '\u{1b}' {
+            in_escape = true;
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+    width
+}
+
+/// # Wrap
+/// Breaks text into lines no wider than the width, between words where it
+/// can and inside words that are too long for a line of their own
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        // Start a new line if the word doesn't fit on this one. Words too
+        // long for any line are split from wherever this line is up to
+        if !line.is_empty() && word.width() <= width && line.width() + 1 + word.width() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            if line.width() + 1 >= width {
+                lines.push(std::mem::take(&mut line));
+            } else {
+                line.push(' ');
+            }
+        }
+
+        for c in word.chars() {
+            if !line.is_empty() && line.width() + c.width().unwrap_or(0) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// # Truncate
+/// Cuts text short to fit the width, marking that it was cut with an
+/// ellipsis
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    for c in text.chars() {
+        if truncated.width() + c.width().unwrap_or(0) + 1 > width {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// # Plain Renderer
+/// Renders each item on its own line as tab separated id, priority, due
+/// date and text, with empty fields left blank:
+/// ```text
+/// ID<TAB>PRIORITY<TAB>YYYY-MM-DD HH:MM<TAB>TEXT
+/// ```
+pub struct PlainRenderer;
+
+impl Renderer for PlainRenderer {
+    fn render(&self, items: &[&TodoItem], context: &Context) -> String {
+        let mut text = String::new();
+        for item in items {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                item.id,
+                item.priority
+                    .map(|p| context.scheme.label(p))
+                    .unwrap_or_default(),
+                item.due
+                    .map(|due| if item.all_day {
+                        item.zone().at(due).format("%Y-%m-%d").to_string()
+                    } else {
+                        context.zone.at(due).format("%Y-%m-%d %H:%M").to_string()
+                    })
+                    .unwrap_or_default(),
+                item.item
+            ));
+        }
+        text
+    }
+}
@@ -0,0 +1,194 @@
+use chrono::{DateTime, Duration, Local};
+
+use crate::dates::{self, DateDialect};
+use crate::error::{Error, Result};
+use crate::filter::Filter;
+use crate::priority::PriorityScheme;
+use crate::todo_item::TodoItem;
+use crate::zone::Zone;
+
+/// # Selector
+/// Picks out the todo items a command acts on from the terms given on the
+/// command line. Every term has to match for an item to be selected.
+///
+///   - `3`, `3,5` or `3-5`: items with these ids
+///   - `overdue`: items that are past their due date
+///   - `pri:2`, `pri:<2` or `pri:>2`: items with this priority, or a more
+///     or less important one. Priorities are written in the scheme of the
+///     list, eg. `pri:<B` or `pri:high`
+///   - `due:friday`, `due:<today` or `due:>"next monday"`: items due on that
+///     day, or before or after that time. Dates are parsed like due dates
+///   - `+project` and `@context`: items in the project or context
+///   - anything else: items containing the text, ignoring case
+///
+/// Items that are done are only selected when they are picked out by id.
+///
+/// ## Data Members
+///   - ids: the ids to select, every id if empty
+///   - overdue: only select overdue items
+///   - filter: the rest of the terms
+#[derive(Clone, Debug, Default)]
+pub struct Selector {
+    pub ids: Vec<u32>,
+    pub overdue: bool,
+    pub filter: Filter,
+}
+
+impl Selector {
+    /// # Parse Selector
+    /// Parses the terms of a selector
+    ///
+    /// ## Args
+    ///   - terms: the terms from the command line
+    ///   - now: the current time, for dates like "today"
+    ///   - dialect: how dates written as numbers are read
+    ///   - zone: the timezone dates are typed in
+    ///   - scheme: how the priorities of the list are written
+    ///
+    /// ## Rets
+    ///   - Result with the selector or an error naming the bad term
+    pub fn parse(
+        terms: &[&str],
+        now: DateTime<Local>,
+        dialect: DateDialect,
+        zone: Zone,
+        scheme: PriorityScheme,
+    ) -> Result<Selector> {
+        let mut selector = Selector::default();
+        let mut text = Vec::new();
+
+        for term in terms {
+            if let Some(priority) = term.strip_prefix("pri:") {
+                selector.parse_priority(priority, scheme)?;
+            } else if let Some(due) = term.strip_prefix("due:") {
+                selector.parse_due(due, now, dialect, zone)?;
+            } else if *term == "overdue" {
+                selector.overdue = true;
+            } else if let Some(ids) = parse_ids(term) {
+                selector.ids.extend(ids?);
+            } else if term.len() > 1 && term.starts_with('+') {
+                selector.filter.projects.push(term[1..].to_string());
+            } else if term.len() > 1 && term.starts_with('@') {
+                selector.filter.contexts.push(term[1..].to_string());
+            } else {
+                text.push(*term);
+            }
+        }
+
+        if !text.is_empty() {
+            selector.filter.text = Some(text.join(" "));
+        }
+        Ok(selector)
+    }
+
+    /// Parses the value of a `pri:` term into the priority range of the
+    /// filter. More important priorities have lower ranks.
+    fn parse_priority(&mut self, value: &str, scheme: PriorityScheme) -> Result<()> {
+        let (operator, label) = split_operator(value);
+        let priority = scheme.parse(label)?;
+
+        match operator {
+            Some('<') => self.filter.max_priority = Some(priority - 1),
+            Some('>') => self.filter.min_priority = Some(priority + 1),
+            _ => {
+                self.filter.min_priority = Some(priority);
+                self.filter.max_priority = Some(priority);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the value of a `due:` term into the due date range of the
+    /// filter. A date without a comparison covers the whole day.
+    fn parse_due(
+        &mut self,
+        value: &str,
+        now: DateTime<Local>,
+        dialect: DateDialect,
+        zone: Zone,
+    ) -> Result<()> {
+        let (operator, date) = split_operator(value);
+        let date = dates::parse_date(date, now, dialect, zone)?;
+
+        match operator {
+            Some('<') => self.filter.due_before = Some(date),
+            Some('>') => self.filter.due_after = Some(date),
+            _ => {
+                let day = zone.at(date).date_naive();
+                let start = zone.localize(day.and_hms_opt(0, 0, 0).unwrap());
+                let end = zone.localize(day.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap());
+                self.filter.due_after = Some(start - Duration::nanoseconds(1));
+                self.filter.due_before = Some(end);
+            }
+        }
+        Ok(())
+    }
+
+    /// # Matches
+    /// Checks if a todo item is picked out by the selector
+    ///
+    /// ## Args
+    ///   - item: the todo item to check
+    ///   - now: the current time, for finding overdue items
+    pub fn matches(&self, item: &TodoItem, now: DateTime<Local>) -> bool {
+        if self.ids.is_empty() {
+            if item.is_done() {
+                return false;
+            }
+        } else if !self.ids.contains(&item.id) {
+            return false;
+        }
+
+        if self.overdue && item.due.is_none_or(|due| due >= now) {
+            return false;
+        }
+
+        self.filter.matches(item)
+    }
+}
+
+/// Splits a leading '<' or '>' off of a value
+fn split_operator(value: &str) -> (Option<char>, &str) {
+    match value.chars().next() {
+        Some(operator) if operator == '<' || operator == '>' => (Some(operator), value[1..].trim()),
+        _ => (None, value.trim()),
+    }
+}
+
+/// # Parse Ids
+/// Parses a comma separated list of ids and ranges of ids, eg. "3,5-7".
+///
+/// ## Rets
+///   - None if the term isn't a list of ids, otherwise Result with the ids
+///     or an error if a range is backwards or an id is zero
+fn parse_ids(term: &str) -> Option<Result<Vec<u32>>> {
+    if term.is_empty()
+        || !term
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ',' || c == '-')
+    {
+        return None;
+    }
+
+    let mut ids = Vec::new();
+    for part in term.split(',') {
+        let bounds: Vec<Option<u32>> = part.split('-').map(|id| id.parse::<u32>().ok()).collect();
+        let range = match bounds.as_slice() {
+            [Some(id)] => *id..=*id,
+            [Some(first), Some(last)] if first <= last => *first..=*last,
+            _ => {
+                return Some(Err(Error::Parse(format!(
+                    "Invalid ids '{}'. Expected ids like 3, 3,5 or 3-5",
+                    term
+                ))))
+            }
+        };
+        if *range.start() == 0 {
+            return Some(Err(Error::Parse(String::from(
+                "Ids need to be positive integers",
+            ))));
+        }
+        ids.extend(range);
+    }
+    Some(Ok(ids))
+}
@@ -0,0 +1,244 @@
+use chrono::{DateTime, Local};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::priority::PriorityScheme;
+use crate::todo_item::TodoItem;
+use crate::urgency::{Coefficients, Scorer};
+
+/// # Sort Field
+/// The fields of a todo item that it can be sorted by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortField {
+    Id,
+    Priority,
+    Due,
+    Text,
+    Created,
+    Completed,
+    Wait,
+    Estimate,
+    Spent,
+    Urgency,
+}
+
+// FromStr trait implementation so fields can be named on the command line
+impl FromStr for SortField {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SortField, Error> {
+        match s.to_lowercase().as_str() {
+            "id" => Ok(SortField::Id),
+            "priority" | "pri" => Ok(SortField::Priority),
+            "due" => Ok(SortField::Due),
+            "text" | "item" => Ok(SortField::Text),
+            "created" => Ok(SortField::Created),
+            "completed" | "done" => Ok(SortField::Completed),
+            "wait" | "start" => Ok(SortField::Wait),
+            "estimate" => Ok(SortField::Estimate),
+            "spent" | "time" => Ok(SortField::Spent),
+            "urgency" => Ok(SortField::Urgency),
+            _ => Err(Error::Parse(format!(
+                "Unknown sort field '{}'. Expected one of {}",
+                s, SORT_FIELDS
+            ))),
+        }
+    }
+}
+
+/// Names of the fields that can be sorted by, for help and error messages
+pub const SORT_FIELDS: &str =
+    "id, priority, due, text, created, completed, wait, estimate, spent, urgency";
+
+/// # Sort Key
+/// A single field to sort by and its direction.
+///
+/// ## Data Members
+///   - field: the field to compare
+///   - descending: whether larger values come first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+/// # Sort Spec
+/// An ordering of todo items made up of several keys. Items are compared by
+/// the first key, then the next key for items that are equal, and so on.
+/// Items that are missing a value are always sorted after those with one,
+/// whichever direction the key sorts in, and items that are equal on every
+/// key are ordered by id so that the output is always the same.
+///
+/// Specs are written as a comma separated list of field names, each
+/// optionally starting with a '-' to sort descending (eg. "due,-priority").
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortSpec {
+    pub keys: Vec<SortKey>,
+}
+
+impl Default for SortSpec {
+    /// The most urgent items first
+    fn default() -> SortSpec {
+        SortSpec {
+            keys: vec![SortKey {
+                field: SortField::Urgency,
+                descending: true,
+            }],
+        }
+    }
+}
+
+// FromStr trait implementation so the spec can be given on the command line
+impl FromStr for SortSpec {
+    type Err = Error;
+
+    /// # Parse Sort Spec
+    /// Parses a comma separated list of fields. The short orders from
+    /// earlier versions are still understood: 'd' and 'da' sort by due date
+    /// ascending, 'dd' by due date descending, 'p' and 'pa' by priority
+    /// ascending, 'pd' by priority descending and 'u' by urgency.
+    fn from_str(s: &str) -> Result<SortSpec, Error> {
+        let shorthand = match s {
+            "d" | "da" => Some("due"),
+            "dd" => Some("-due"),
+            "p" | "pa" => Some("priority"),
+            "pd" => Some("-priority"),
+            "u" => Some("-urgency"),
+            _ => None,
+        };
+
+        let mut keys = Vec::new();
+        for key in shorthand.unwrap_or(s).split(',') {
+            let key = key.trim();
+            let (name, descending) = match key.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (key.strip_prefix('+').unwrap_or(key), false),
+            };
+            keys.push(SortKey {
+                field: name.parse::<SortField>()?,
+                descending,
+            });
+        }
+
+        Ok(SortSpec { keys })
+    }
+}
+
+// Display trait implementation that writes the spec back out
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .map(|key| {
+                let name = format!("{:?}", key.field).to_lowercase();
+                if key.descending {
+                    format!("-{}", name)
+                } else {
+                    name
+                }
+            })
+            .collect();
+        write!(f, "{}", keys.join(","))
+    }
+}
+
+impl SortSpec {
+    /// Whether any key of the spec sorts by the given field
+    pub fn uses(&self, field: SortField) -> bool {
+        self.keys.iter().any(|key| key.field == field)
+    }
+}
+
+/// # Sort Todo Items
+/// Sorts the todo items in place by the spec.
+///
+/// ## Args
+///   - items: the todo items to sort
+///   - spec: how to sort them
+///   - coefficients: urgency weights, used if the spec sorts by urgency
+///   - scheme: how the priorities are written, used for urgency
+///   - now: the current time, used for urgency and time spent
+pub fn sort(
+    items: &mut [TodoItem],
+    spec: &SortSpec,
+    coefficients: &Coefficients,
+    scheme: PriorityScheme,
+    now: DateTime<Local>,
+) {
+    // Urgency depends on the rest of the list so it is worked out up front
+    let scores: Vec<f64> = if spec.uses(SortField::Urgency) {
+        let scorer = Scorer::new(items, coefficients, scheme, now);
+        items.iter().map(|item| scorer.score(item)).collect()
+    } else {
+        vec![0.0; items.len()]
+    };
+
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by(|&a, &b| {
+        spec.keys
+            .iter()
+            .map(|key| compare(key, &items[a], &items[b], scores[a], scores[b], now))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+            .then(items[a].id.cmp(&items[b].id))
+    });
+
+    let sorted: Vec<TodoItem> = order.iter().map(|&index| items[index].clone()).collect();
+    items.clone_from_slice(&sorted);
+}
+
+/// # Compare
+/// Compares two todo items by a single key
+fn compare(
+    key: &SortKey,
+    a: &TodoItem,
+    b: &TodoItem,
+    a_score: f64,
+    b_score: f64,
+    now: DateTime<Local>,
+) -> Ordering {
+    let descending = key.descending;
+    match key.field {
+        SortField::Id => missing_last(&Some(a.id), &Some(b.id), descending),
+        SortField::Priority => missing_last(&a.priority, &b.priority, descending),
+        SortField::Due => missing_last(&a.due, &b.due, descending),
+        SortField::Text => missing_last(
+            &Some((a.item.to_lowercase(), &a.item)),
+            &Some((b.item.to_lowercase(), &b.item)),
+            descending,
+        ),
+        SortField::Created => missing_last(&a.created, &b.created, descending),
+        SortField::Completed => missing_last(&a.completed, &b.completed, descending),
+        SortField::Wait => missing_last(&a.wait, &b.wait, descending),
+        SortField::Estimate => missing_last(&a.estimate, &b.estimate, descending),
+        SortField::Spent => {
+            let spent =
+                |item: &TodoItem| Some(item.time_spent(now)).filter(|_| !item.time.is_empty());
+            missing_last(&spent(a), &spent(b), descending)
+        }
+        SortField::Urgency => {
+            let ordering = a_score.partial_cmp(&b_score).unwrap_or(Ordering::Equal);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+    }
+}
+
+/// # Compare Missing Last
+/// Compares two optional values so that None always comes after Some,
+/// regardless of the direction the present values are sorted in.
+fn missing_last<T: Ord>(a: &Option<T>, b: &Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(a),
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
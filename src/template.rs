@@ -0,0 +1,376 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::dates::end_of_day;
+use crate::error::{Error, Result};
+use crate::priority::PriorityScheme;
+use crate::todo_item::TodoItem;
+use crate::todo_list::TodoList;
+use crate::zone::Zone;
+
+/// # Offset
+/// When a template item is due, relative to the day the template is applied
+/// for.
+///
+/// ## Data Members
+///   - days: days after the anchor day, or before it if negative
+///   - time: the time of day the item is due, or None if it is due all day
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Offset {
+    pub days: i64,
+    pub time: Option<NaiveTime>,
+}
+
+/// # Template Item
+/// One item of a template.
+///
+/// ## Data Members
+///   - priority: rank of the priority of the item
+///   - offset: when the item is due, None if it has no due date
+///   - text: the todo text, with any projects and contexts
+///   - subtasks: items that have to be done before this one
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TemplateItem {
+    pub priority: Option<i8>,
+    pub offset: Option<Offset>,
+    pub text: String,
+    pub subtasks: Vec<TemplateItem>,
+}
+
+/// # Template
+/// A set of items for a process that is done again and again, like a
+/// release. Templates are stored as files in $CONFIG/todo/templates, one
+/// item per line in the form
+/// ```text
+/// [PRIORITY]{OFFSET} TEXT
+/// ```
+/// where the offset is a number of days from the anchor like `-3d`, `0d` or
+/// `+1w`, optionally followed by a time like `-1d 15:00`, and both can be
+/// left empty. Priorities are written in the scheme of the list. Indented
+/// lines are subtasks of the item above them, which depends on them. Blank
+/// lines and lines starting with '#' are skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Template {
+    pub items: Vec<TemplateItem>,
+}
+
+impl Template {
+    /// # Directory
+    /// Gets the directory templates are stored in, $CONFIG/todo/templates
+    pub fn dir() -> Result<PathBuf> {
+        match dirs::config_dir() {
+            Some(mut path) => {
+                path.push("todo");
+                path.push("templates");
+                Ok(path)
+            }
+            None => Err(Error::NoConfigDir),
+        }
+    }
+
+    /// # Path
+    /// Gets the file a template is stored in. Names are limited to letters,
+    /// digits, '-' and '_' so that they can't point outside of the template
+    /// directory.
+    pub fn path(name: &str) -> Result<PathBuf> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::Parse(format!(
+                "Invalid template name '{}'. Use letters, digits, - and _",
+                name
+            )));
+        }
+        Ok(Template::dir()?.join(name))
+    }
+
+    /// # Names
+    /// Gets the names of every saved template, in alphabetical order
+    pub fn names() -> Result<Vec<String>> {
+        let mut names = match fs::read_dir(Template::dir()?) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_file())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        names.sort();
+        Ok(names)
+    }
+
+    /// # Load
+    /// Reads a saved template
+    ///
+    /// ## Args
+    ///   - name: the name of the template
+    ///   - scheme: how priorities are written
+    pub fn load(name: &str, scheme: PriorityScheme) -> Result<Template> {
+        match fs::read_to_string(Template::path(name)?) {
+            Ok(text) => Template::parse(&text, scheme),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(Error::NoSuchTemplate(name.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// # Save
+    /// Writes the template to its file, creating the template directory if
+    /// it doesn't exist
+    ///
+    /// ## Args
+    ///   - name: the name of the template
+    ///   - scheme: how priorities are written
+    ///
+    /// ## Rets
+    ///   - Result with where the template was saved
+    pub fn save(&self, name: &str, scheme: PriorityScheme) -> Result<PathBuf> {
+        let path = Template::path(name)?;
+        fs::create_dir_all(Template::dir()?)?;
+        fs::write(&path, self.write(scheme))?;
+        Ok(path)
+    }
+
+    /// # Parse Template
+    /// Reads the text of a template file
+    ///
+    /// ## Args
+    ///   - text: the contents of the file
+    ///   - scheme: how priorities are written
+    ///
+    /// ## Rets
+    ///   - Result with the template or an error naming the bad line
+    pub fn parse(text: &str, scheme: PriorityScheme) -> Result<Template> {
+        lazy_static! {
+            static ref LINE_RE: Regex =
+                Regex::new(r"^\[([^\]]*)\]\{(?:([+-]?\d+)([dw])(?: (\d{1,2}:\d{2}))?)?\} (.+)$")
+                    .unwrap();
+        }
+
+        let mut template = Template::default();
+        for (number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                Error::Parse(format!(
+                    "Line {} of the template {}: {}",
+                    number + 1,
+                    reason,
+                    line
+                ))
+            };
+
+            let cap = LINE_RE
+                .captures(trimmed)
+                .ok_or_else(|| invalid("isn't an item"))?;
+            let priority = match &cap[1] {
+                "" => None,
+                priority => Some(
+                    scheme
+                        .parse(priority)
+                        .map_err(|_| invalid("has a priority outside of the scheme"))?,
+                ),
+            };
+            let offset = match (cap.get(2), cap.get(3)) {
+                (Some(count), Some(unit)) => {
+                    // The regex guarantees the count is a number
+                    let count = count.as_str().parse::<i64>().unwrap();
+                    let time = match cap.get(4) {
+                        Some(time) => Some(
+                            NaiveTime::parse_from_str(time.as_str(), "%H:%M")
+                                .map_err(|_| invalid("has an invalid time"))?,
+                        ),
+                        None => None,
+                    };
+                    Some(Offset {
+                        days: if unit.as_str() == "w" {
+                            count * 7
+                        } else {
+                            count
+                        },
+                        time,
+                    })
+                }
+                _ => None,
+            };
+            let item = TemplateItem {
+                priority,
+                offset,
+                text: cap[5].to_string(),
+                subtasks: Vec::new(),
+            };
+
+            // Indented lines belong to the last item that isn't
+            if line.starts_with(char::is_whitespace) {
+                template
+                    .items
+                    .last_mut()
+                    .ok_or_else(|| invalid("is a subtask without an item above it"))?
+                    .subtasks
+                    .push(item);
+            } else {
+                template.items.push(item);
+            }
+        }
+
+        if template.items.is_empty() {
+            return Err(Error::Parse(String::from("The template has no items")));
+        }
+        Ok(template)
+    }
+
+    /// # Write Template
+    /// Writes the template in the form it is stored in
+    ///
+    /// ## Args
+    ///   - scheme: how priorities are written
+    pub fn write(&self, scheme: PriorityScheme) -> String {
+        let line = |item: &TemplateItem| {
+            let offset = item.offset.map_or(String::new(), |offset| {
+                let days = format!("{:+}d", offset.days);
+                match offset.time {
+                    Some(time) => format!("{} {}", days, time.format("%H:%M")),
+                    None => days,
+                }
+            });
+            format!(
+                "[{}]{{{}}} {}",
+                item.priority.map_or(String::new(), |p| scheme.label(p)),
+                offset,
+                item.text
+            )
+        };
+
+        let mut text =
+            String::from("# [PRIORITY]{DAYS FROM THE ANCHOR} TEXT. Indented lines are subtasks\n");
+        for item in &self.items {
+            text.push_str(&line(item));
+            text.push('\n');
+            for subtask in &item.subtasks {
+                text.push_str("    ");
+                text.push_str(&line(subtask));
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// # From Items
+    /// Captures a group of todo items as a template. Items that only one
+    /// other item in the group depends on become its subtasks, and due dates
+    /// become offsets from the anchor day.
+    ///
+    /// ## Args
+    ///   - items: the items to capture
+    ///   - anchor: the day offsets are counted from
+    ///   - zone: the timezone days are counted in
+    pub fn from_items(items: &[&TodoItem], anchor: NaiveDate, zone: Zone) -> Template {
+        let template_item = |item: &TodoItem| TemplateItem {
+            priority: item.priority,
+            offset: item.due.map(|due| {
+                let due = if item.all_day {
+                    item.zone().at(due).date_naive()
+                } else {
+                    zone.at(due).date_naive()
+                };
+                let days = (due - anchor).num_days();
+                Offset {
+                    days,
+                    time: (!item.all_day).then(|| zone.at(item.due.unwrap()).time()),
+                }
+            }),
+            text: item.item.clone(),
+            subtasks: Vec::new(),
+        };
+
+        // An item is a subtask if exactly one item in the group depends on
+        // it and it has no subtasks of its own, since templates only go one
+        // level deep
+        let ids: HashSet<u32> = items.iter().map(|item| item.id).collect();
+        let parents = |id: u32| {
+            items
+                .iter()
+                .filter(|item| item.depends.contains(&id))
+                .count()
+        };
+        let has_subtasks = |item: &TodoItem| {
+            item.depends
+                .iter()
+                .any(|id| ids.contains(id) && parents(*id) == 1)
+        };
+        let is_subtask = |item: &TodoItem| parents(item.id) == 1 && !has_subtasks(item);
+
+        let mut template = Template::default();
+        for item in items.iter().filter(|item| !is_subtask(item)) {
+            let mut parent = template_item(item);
+            parent.subtasks = items
+                .iter()
+                .filter(|subtask| item.depends.contains(&subtask.id) && is_subtask(subtask))
+                .map(|subtask| template_item(subtask))
+                .collect();
+            template.items.push(parent);
+        }
+        template
+    }
+
+    /// # Apply
+    /// Adds the items of the template to a list, due relative to the anchor
+    /// day. Items without a time are due all day. Each item depends on its
+    /// subtasks. The list has to be saved for the items to be written.
+    ///
+    /// ## Args
+    ///   - list: the list to add the items to
+    ///   - anchor: the day offsets are counted from
+    ///   - zone: the timezone the days are in
+    ///
+    /// ## Rets
+    ///   - the ids of the items that were added, in template order
+    pub fn apply(&self, list: &mut TodoList, anchor: NaiveDate, zone: Zone) -> Vec<u32> {
+        let todo_item = |item: &TemplateItem| {
+            let mut todo_item = TodoItem {
+                priority: item.priority,
+                item: item.text.clone(),
+                ..TodoItem::default()
+            };
+            if let Some(offset) = item.offset {
+                let day = anchor + Duration::days(offset.days);
+                match offset.time {
+                    Some(time) => todo_item.due = Some(zone.localize(day.and_time(time))),
+                    None => {
+                        todo_item.due = Some(end_of_day(day, zone));
+                        todo_item.all_day = true;
+                    }
+                }
+                todo_item.tz = zone.named();
+            }
+            todo_item
+        };
+
+        let mut added = Vec::new();
+        for item in &self.items {
+            let id = list.add(todo_item(item));
+            added.push(id);
+
+            let mut depends = Vec::new();
+            for subtask in &item.subtasks {
+                let subtask_id = list.add(todo_item(subtask));
+                added.push(subtask_id);
+                depends.push(subtask_id);
+            }
+            if let Some(parent) = list.get_mut(id) {
+                parent.depends = depends;
+            }
+        }
+        added
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Duration, Local};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::todo_item::{format_timestamp, parse_timestamp};
+
+/// # Time Interval
+/// A stretch of time spent working on a todo item.
+///
+/// ## Data Members
+///   - start: when work started
+///   - end: when work stopped. None while the item is still being worked on
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeInterval {
+    pub start: DateTime<Local>,
+    pub end: Option<DateTime<Local>>,
+}
+
+impl TimeInterval {
+    /// # Duration
+    /// Gets how long the interval lasted. Intervals that are still running
+    /// are measured up until now.
+    ///
+    /// ## Args
+    ///   - now: the current time
+    pub fn duration(&self, now: DateTime<Local>) -> Duration {
+        self.end.unwrap_or(now) - self.start
+    }
+
+    /// Whether the interval is still running
+    pub fn is_running(&self) -> bool {
+        self.end.is_none()
+    }
+}
+
+// Display trait implementation for the time interval
+impl fmt::Display for TimeInterval {
+    /// # Display function
+    /// Serializes the interval as it is stored in the data file, the start
+    /// and end separated by a slash. Running intervals have no end:
+    /// ```text
+    /// START/END
+    /// START/
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/", format_timestamp(self.start))?;
+        if let Some(end) = self.end {
+            write!(f, "{}", format_timestamp(end))?;
+        }
+        Ok(())
+    }
+}
+
+// FromStr trait implementation for the time interval
+impl FromStr for TimeInterval {
+    type Err = Error;
+
+    /// # Parse a Time Interval
+    /// Parses an interval as it is stored in the data file
+    fn from_str(s: &str) -> Result<TimeInterval, Error> {
+        let invalid = || Error::Parse(format!("Invalid time interval '{}'", s));
+
+        let split = s.find('/').ok_or_else(invalid)?;
+        let start = parse_timestamp(&s[..split]).map_err(|_| invalid())?;
+        let end = match &s[split + 1..] {
+            "" => None,
+            end => Some(parse_timestamp(end).map_err(|_| invalid())?),
+        };
+
+        Ok(TimeInterval { start, end })
+    }
+}
+
+/// # Format Duration
+/// Formats a duration as hours and minutes for display (eg. "1h 05m")
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use std::collections::BTreeMap;
+
+use crate::dates::start_of_day;
+use crate::todo_item::TodoItem;
+use crate::zone::Zone;
+
+/// # Timesheet Entry
+/// Time spent on a single item on a single day.
+///
+/// ## Data Members
+///   - date: the day the time was spent
+///   - id: id of the item
+///   - item: text of the item
+///   - projects: projects the item belongs to
+///   - duration: how much time was spent
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub date: NaiveDate,
+    pub id: u32,
+    pub item: String,
+    pub projects: Vec<String>,
+    pub duration: Duration,
+}
+
+/// # Timesheet
+/// Summary of the time tracked against todo items over a range of days.
+/// Intervals that cross midnight are split between the days they cover, and
+/// only the part of an interval that falls within the range is counted.
+///
+/// ## Data Members
+///   - from: first day in the range (inclusive)
+///   - to: last day in the range (inclusive)
+///   - entries: time spent per item per day, ordered by day and then id
+#[derive(Clone, Debug)]
+pub struct Timesheet {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub entries: Vec<Entry>,
+}
+
+impl Timesheet {
+    /// # New Timesheet
+    /// Builds a timesheet from the time tracked against the given items
+    ///
+    /// ## Args
+    ///   - items: the todo items to report on
+    ///   - from: first day to report on (inclusive)
+    ///   - to: last day to report on (inclusive)
+    ///   - now: the current time, used to measure running intervals
+    ///   - zone: the timezone the days are in
+    pub fn new(
+        items: &[TodoItem],
+        from: NaiveDate,
+        to: NaiveDate,
+        now: DateTime<Local>,
+        zone: Zone,
+    ) -> Timesheet {
+        let range_start = start_of_day(from, zone);
+        let range_end = start_of_day(to + Duration::days(1), zone);
+
+        let mut spent: BTreeMap<(NaiveDate, u32), (Duration, &TodoItem)> = BTreeMap::new();
+        for item in items {
+            for interval in &item.time {
+                let mut start = interval.start.max(range_start);
+                let end = interval.end.unwrap_or(now).min(range_end);
+
+                // Walk through the interval a day at a time
+                while start < end {
+                    let day = zone.at(start).date_naive();
+                    let day_end = start_of_day(day + Duration::days(1), zone).min(end);
+                    let entry = spent
+                        .entry((day, item.id))
+                        .or_insert((Duration::zero(), item));
+                    entry.0 += day_end - start;
+                    start = day_end;
+                }
+            }
+        }
+
+        Timesheet {
+            from,
+            to,
+            entries: spent
+                .into_iter()
+                .map(|((date, id), (duration, item))| Entry {
+                    date,
+                    id,
+                    item: item.item.clone(),
+                    projects: item.projects().iter().map(|p| p.to_string()).collect(),
+                    duration,
+                })
+                .collect(),
+        }
+    }
+
+    /// Total time spent over the whole range
+    pub fn total(&self) -> Duration {
+        self.entries
+            .iter()
+            .fold(Duration::zero(), |total, entry| total + entry.duration)
+    }
+
+    /// # Time by Item
+    /// Gets the time spent on each item, ordered by id
+    ///
+    /// ## Rets
+    ///   - the id, text and time spent for each item
+    pub fn by_item(&self) -> Vec<(u32, String, Duration)> {
+        let mut totals: BTreeMap<u32, (String, Duration)> = BTreeMap::new();
+        for entry in &self.entries {
+            let total = totals
+                .entry(entry.id)
+                .or_insert((entry.item.clone(), Duration::zero()));
+            total.1 += entry.duration;
+        }
+        totals
+            .into_iter()
+            .map(|(id, (item, duration))| (id, item, duration))
+            .collect()
+    }
+
+    /// # Time by Project
+    /// Gets the time spent on each project, ordered by name. An item in
+    /// several projects counts towards each of them, and items without a
+    /// project are grouped under an empty name.
+    pub fn by_project(&self) -> Vec<(String, Duration)> {
+        let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+        for entry in &self.entries {
+            if entry.projects.is_empty() {
+                let total = totals.entry(String::new()).or_insert_with(Duration::zero);
+                *total += entry.duration;
+            }
+            for project in &entry.projects {
+                let total = totals.entry(project.clone()).or_insert_with(Duration::zero);
+                *total += entry.duration;
+            }
+        }
+        totals.into_iter().collect()
+    }
+
+    /// # Time by Day
+    /// Gets the time spent on each day that had any time tracked, in order
+    pub fn by_day(&self) -> Vec<(NaiveDate, Duration)> {
+        let mut totals: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+        for entry in &self.entries {
+            let total = totals.entry(entry.date).or_insert_with(Duration::zero);
+            *total += entry.duration;
+        }
+        totals.into_iter().collect()
+    }
+
+    /// # CSV Export
+    /// Writes every entry as a CSV row with a header. Hours are decimal so
+    /// they can be summed in a spreadsheet, and projects are separated by
+    /// spaces.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,id,item,projects,hours\n");
+        for entry in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.2}\n",
+                entry.date.format("%Y-%m-%d"),
+                entry.id,
+                csv_field(&entry.item),
+                csv_field(&entry.projects.join(" ")),
+                hours(entry.duration)
+            ));
+        }
+        csv
+    }
+}
+
+/// Converts a duration to decimal hours
+pub fn hours(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+/// # CSV Field
+/// Quotes a CSV field if it contains a comma, quote or newline
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,207 +1,346 @@
-use chrono::offset::TimeZone;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Duration, Local, NaiveDate, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
 use std::fmt;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::{self, BufRead, Error, ErrorKind, Write};
+use std::str::FromStr;
 
-extern crate regex;
-use regex::Regex;
+use crate::dates::end_of_day;
+use crate::error::Error;
+use crate::time_tracking::TimeInterval;
+use crate::zone::Zone;
+
+/// Format used to write due dates to the data file. Due dates are written in
+/// UTC
+const DUE_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.9f %:z";
+
+/// Format used to write all day due dates to the data file
+const DAY_FORMAT: &str = "%Y-%m-%d";
 
 /// # Todo Item Object
 /// Contains all information about the todo list item.
 ///
 /// ## Data Members
-///     - priority: numerical priority of the todo item
-///     - item: text that explains what to do
-///     - due: the due date of the todo item
+///   - id: number identifying the item within its list. 0 until the item
+///     is added to a list
+///   - priority: rank of the priority of the todo item, 0 being the most
+///     important. How it is written depends on the priority scheme of its
+///     list
+///   - item: text that explains what to do
+///   - due: the due date of the todo item
+///   - all_day: whether the item is due on a day rather than at a time. The
+///     due date is then the last moment of that day, so the item is only
+///     overdue once the day is over
+///   - tz: the timezone the due date was set in, so that it can be moved by
+///     whole days without its time changing when the clocks change. The day
+///     of an item due all day is the day in this timezone
+///   - created: when the item was added to its list
+///   - completed: when the item was done. None while it is still open
+///   - wait: the item is hidden until this date
+///   - estimate: how long the item is expected to take
+///   - depends: ids of the items that have to be done before this one
+///   - time: intervals of time spent working on the item
+///   - links: URLs and absolute paths of files that go with the item
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct TodoItem {
+    pub id: u32,
     pub priority: Option<i8>,
     pub item: String,
     pub due: Option<DateTime<Local>>,
+    pub all_day: bool,
+    pub tz: Option<Tz>,
+    pub created: Option<DateTime<Local>>,
+    pub completed: Option<DateTime<Local>>,
+    pub wait: Option<DateTime<Local>>,
+    pub estimate: Option<Duration>,
+    pub depends: Vec<u32>,
+    pub time: Vec<TimeInterval>,
+    pub links: Vec<String>,
 }
 
 // Display trait implementation for the todo item
 impl fmt::Display for TodoItem {
     /// # Display function
-    /// Displays the todo item nicely in the output location
+    /// Serializes the todo item into the data file format. Attributes are
+    /// separated from the text and each other by tabs:
+    /// ```text
+    /// [PRIORITY]{DATE OR DAY} TEXT\tid=ID\tcreated=DATE\tcompleted=DATE\twait=DATE\ttz=ZONE\testimate=MINUTES\tdepends=ID\ttime=START/END\tlink=LINK
+    /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Instantiate a string to hold to todo information
         let mut todo_text = String::new();
 
         // Add the priority if there is one
-        todo_text.push_str("[");
+        todo_text.push('[');
         if let Some(priority) = self.priority {
             todo_text.push_str(&priority.to_string());
         }
-        todo_text.push_str("]");
+        todo_text.push(']');
 
         // Add the due date if there is one
-        todo_text.push_str("{");
+        todo_text.push('{');
         if let Some(due) = self.due {
-            todo_text.push_str(&due.to_string());
+            if self.all_day {
+                let day = self.zone().at(due).date_naive();
+                todo_text.push_str(&day.format(DAY_FORMAT).to_string());
+            } else {
+                todo_text.push_str(&due.with_timezone(&Utc).format(DUE_FORMAT).to_string());
+            }
         }
         todo_text.push_str("} ");
 
-        // Add the todo text (always present)
-        todo_text.push_str(&self.item);
+        // Add the todo text (always present). Tabs would be read back as
+        // the start of an attribute and line breaks as the start of another
+        // item so they are replaced
+        todo_text.push_str(&self.item.replace(['\t', '\n', '\r'], " "));
+
+        // Add the attributes
+        todo_text.push_str(&format!("\tid={}", self.id));
+        if let Some(created) = self.created {
+            todo_text.push_str(&format!("\tcreated={}", format_timestamp(created)));
+        }
+        if let Some(completed) = self.completed {
+            todo_text.push_str(&format!("\tcompleted={}", format_timestamp(completed)));
+        }
+        if let Some(wait) = self.wait {
+            todo_text.push_str(&format!("\twait={}", format_timestamp(wait)));
+        }
+        if let Some(tz) = self.tz {
+            todo_text.push_str(&format!("\ttz={}", tz.name()));
+        }
+        if let Some(estimate) = self.estimate {
+            todo_text.push_str(&format!("\testimate={}", estimate.num_minutes()));
+        }
+        for depends in &self.depends {
+            todo_text.push_str(&format!("\tdepends={}", depends));
+        }
+        for interval in &self.time {
+            todo_text.push_str(&format!("\ttime={}", interval));
+        }
+        for link in &self.links {
+            todo_text.push_str(&format!("\tlink={}", link));
+        }
 
         write!(f, "{}", todo_text)
     }
 }
 
-impl TodoItem {
-    /// # Get All Todo Items
-    /// Gather all todo items from the default $CONFIG/todo/todo file
-    pub fn get_items() -> Vec<TodoItem> {
-        // Instantiate a new vector to hold the todo items
-        let mut todo_items: Vec<TodoItem> = Vec::new();
-
-        // Try and get the data file
-        let data_file = match TodoItem::get_data_file(OpenOptions::new().read(true)) {
-            Ok(file) => file,
-            Err(e) => {
-                error!("Couldn't open data file. {}", e);
-                return todo_items;
-            }
-        };
+// FromStr trait implementation for the todo item
+impl FromStr for TodoItem {
+    type Err = Error;
 
-        // Parse each todo item into the todo items list.
-        for line in io::BufReader::new(data_file).lines() {
-            match line {
-                Ok(line) => {
-                    if let Some(todo_item) = TodoItem::parse_todo_item(line) {
-                        todo_items.push(todo_item);
-                    }
-                }
-                Err(e) => {
-                    error!("Couldn't read line of todo file. {}", e);
-                    return todo_items;
-                }
-            }
-        }
-
-        // Return the todo items
-        todo_items
-    }
-
-    fn parse_todo_item(line: String) -> Option<TodoItem> {
+    /// # Parse a Todo Item
+    /// Parses a single line of the data file back into a todo item. This is
+    /// the inverse of the Display implementation.
+    fn from_str(line: &str) -> Result<TodoItem, Error> {
         // Create a lazy_static object for a regex that matches the
         // todo list item and captures the different parts of it
-        // the regex can be explored here: https://regex101.com/r/bH46KS/2
         lazy_static! {
             static ref TODO_RE: Regex = Regex::new(
-                r"\[([0-9]?)\]\{(\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}.\d{9} [+|-]\d{2}:\d{2})?\} ([\w|\s]*)",
-            ).unwrap();
+                r"^\[([0-9]{0,2})\]\{(\d{4}-\d{2}-\d{2}(?: \d{2}:\d{2}:\d{2}(?:\.\d+)? [+-]\d{2}:\d{2})?)?\} ([^\t]*)((?:\t[^\t]*)*)$",
+            )
+            .unwrap();
         }
 
-        // Stuff the todo object with the captured data
+        let cap = match TODO_RE.captures(line) {
+            Some(cap) => cap,
+            None => return Err(Error::Parse(format!("Unrecognized todo item: {}", line))),
+        };
+
+        // The priority group only ever matches up to two digits so it will
+        // always parse if it is present
+        let priority = cap
+            .get(1)
+            .and_then(|priority| priority.as_str().parse::<i8>().ok());
+
         let mut todo_item = TodoItem {
-            item: "".to_string(),
-            priority: None,
-            due: None,
+            priority,
+            item: cap[3].to_string(),
+            ..TodoItem::default()
         };
-        for cap in TODO_RE.captures_iter(&line) {
-            //TODO Silent errors are bad
-            let priority = match cap.get(1) {
-                Some(priority) => match priority.as_str().parse::<i8>() {
-                    Ok(priority) => Some(priority),
-                    Err(_) => None,
-                },
-                None => None,
-            };
-            let due = match cap.get(2) {
-                Some(due) => {
-                    match Local.datetime_from_str(due.as_str(), "%Y-%m-%d %H:%M:%S.%f %:z") {
-                        Ok(due) => Some(due),
-                        Err(_) => None,
-                    }
-                }
-                None => None,
-            };
-            let item = match cap.get(3) {
-                Some(item) => item.as_str(),
-                None => return None,
-            };
-
-            todo_item.item = item.to_string();
-            todo_item.priority = priority;
-            todo_item.due = due;
+
+        // Every attribute is a key=value pair after a tab
+        for attribute in cap[4].split('\t').skip(1) {
+            todo_item.set_attribute(attribute)?;
+        }
+
+        // The due date is read last since the day of an item due all day is
+        // in the timezone given by its attributes
+        if let Some(due) = cap.get(2) {
+            let (due, all_day) = parse_due(due.as_str(), todo_item.zone())?;
+            todo_item.due = Some(due);
+            todo_item.all_day = all_day;
         }
-        Some(todo_item)
+
+        Ok(todo_item)
     }
+}
 
-    /// # Get Data File
-    /// Gets the data file for the app on the computer. Uses
-    /// the dirs crate to find the configuration dir. Opens the file
-    /// in the argument specified way and returns a io::Result containing
-    /// the file
+impl TodoItem {
+    /// # Set Attribute
+    /// Reads a single `key=value` attribute from the data file into the item
     ///
     /// ## Args
-    ///     - open_options: OpenOptions that describes how to open the file
+    ///   - attribute: the attribute as it is written in the data file
     ///
     /// ## Rets
-    ///     - Result<File> with the file or an error
-    fn get_data_file(open_options: &OpenOptions) -> std::io::Result<File> {
-        let mut data_file = match dirs::config_dir() {
-            // If the config path exists then push through
-            // to the config file for this tool
-            Some(mut path) => {
-                path.push("todo");
-                path.push("todo");
-                path
-            }
-            // If the config path doesn't exist then return
-            // that error
+    ///   - Result that is an error if the attribute is malformed or unknown
+    pub(crate) fn set_attribute(&mut self, attribute: &str) -> Result<(), Error> {
+        let (key, value) = match attribute.find('=') {
+            Some(split) => (&attribute[..split], &attribute[split + 1..]),
             None => {
-                return Err(Error::new(
-                    ErrorKind::NotFound,
-                    "Could not find config directory",
-                ));
+                return Err(Error::Parse(format!("Malformed attribute '{}'", attribute)));
             }
         };
 
-        // Check the validity of the save file location. The
-        // path object itself makes no assumptions about the existance
-        // of the file.
-        if !data_file.exists() {
-            // If the config file doesn't exist then pop back to the parent
-            // directory and create it and all its' parents
-            data_file.pop();
-            if let Err(e) = std::fs::create_dir_all(data_file.as_path()) {
-                return Err(e);
+        match key {
+            "id" => {
+                self.id = value
+                    .parse::<u32>()
+                    .map_err(|_| Error::Parse(format!("Invalid id '{}'", value)))?;
             }
-            data_file.push("todo");
-
-            // Alert the user that the file is being created
-            // TODO: Might make sense to ask for confirmation
-            info!("File doesn't exist. Creating it.");
-
-            // Try and create the file. We can just return the
-            // create function because this returns a result
-            if let Err(e) = File::create(data_file.as_path()) {
-                return Err(e);
+            "created" => self.created = Some(parse_timestamp(value)?),
+            "completed" => self.completed = Some(parse_timestamp(value)?),
+            "wait" => self.wait = Some(parse_timestamp(value)?),
+            "tz" => {
+                self.tz = Some(
+                    value
+                        .parse::<Tz>()
+                        .map_err(|_| Error::Parse(format!("Invalid timezone '{}'", value)))?,
+                );
+            }
+            "estimate" => {
+                let minutes = value
+                    .parse::<i64>()
+                    .map_err(|_| Error::Parse(format!("Invalid estimate '{}'", value)))?;
+                let estimate = Duration::try_minutes(minutes)
+                    .ok_or_else(|| Error::Parse(format!("Estimate '{}' is too long", value)))?;
+                self.estimate = Some(estimate);
+            }
+            "depends" => {
+                self.depends.push(
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| Error::Parse(format!("Invalid dependency '{}'", value)))?,
+                );
             }
+            "time" => self.time.push(value.parse::<TimeInterval>()?),
+            "link" => self.links.push(value.to_string()),
+            _ => return Err(Error::Parse(format!("Unknown attribute '{}'", key))),
         }
 
-        open_options.open(data_file.as_path())
+        Ok(())
     }
 
-    /// # Save
-    /// Save the todo to the config file located in $HOME/.config/todo/todo.
-    /// The format is as follows:
-    ///         [PRIORITY] {DATE} TEXT
-    pub fn save(&self) {
-        // Get the data file
-        match TodoItem::get_data_file(OpenOptions::new().append(true)) {
-            // If there is a data file then write the todo item to it
-            Ok(mut data_file) => {
-                if let Err(e) = writeln!(data_file, "{}", self) {
-                    error!("Unable to write to todo file. {}.", e);
-                }
-            }
-            // Log to the user if unable to write the file
-            Err(e) => println!("Unable to open todo file. {}.", e),
+    /// The timezone the due date of the item was set in, which the day
+    /// of an item due all day is in
+    pub fn zone(&self) -> Zone {
+        Zone::from(self.tz)
+    }
+
+    /// # Due Zone
+    /// Gets the timezone the day the item is due is counted in. That is the
+    /// timezone an item due all day was set in, since its day ends at
+    /// midnight there, and otherwise the one dates are shown in.
+    ///
+    /// ## Args
+    ///   - zone: the timezone dates are shown in
+    pub fn due_zone(&self, zone: Zone) -> Zone {
+        if self.all_day {
+            self.zone()
+        } else {
+            zone
         }
     }
+
+    /// Whether the item has been done
+    pub fn is_done(&self) -> bool {
+        self.completed.is_some()
+    }
+
+    /// # Is Waiting
+    /// Whether the item is still hidden until its wait date
+    ///
+    /// ## Args
+    ///   - now: the current time
+    pub fn is_waiting(&self, now: DateTime<Local>) -> bool {
+        self.wait.is_some_and(|wait| wait > now)
+    }
+
+    /// # Projects
+    /// Gets the projects the item belongs to. Projects are written in the
+    /// item text as words starting with a '+' (eg. "+garden"). Punctuation
+    /// at the end of the word isn't part of the project.
+    pub fn projects(&self) -> Vec<&str> {
+        self.item
+            .split_whitespace()
+            .filter(|word| word.starts_with('+'))
+            .map(|word| word[1..].trim_end_matches(|c: char| c.is_ascii_punctuation()))
+            .filter(|project| !project.is_empty())
+            .collect()
+    }
+
+    /// # Contexts
+    /// Gets the contexts the item can be done in. Contexts are written in the
+    /// item text as words starting with an '@' (eg. "@phone").
+    pub fn contexts(&self) -> Vec<&str> {
+        self.item
+            .split_whitespace()
+            .filter(|word| word.starts_with('@'))
+            .map(|word| word[1..].trim_end_matches(|c: char| c.is_ascii_punctuation()))
+            .filter(|context| !context.is_empty())
+            .collect()
+    }
+
+    /// # Running Interval
+    /// Gets the time interval that is currently being tracked for the item
+    pub fn running_interval(&self) -> Option<&TimeInterval> {
+        self.time.iter().find(|interval| interval.is_running())
+    }
+
+    /// # Time Spent
+    /// Gets the total time spent working on the item
+    ///
+    /// ## Args
+    ///   - now: the current time, used to measure any running interval
+    pub fn time_spent(&self, now: DateTime<Local>) -> Duration {
+        self.time.iter().fold(Duration::zero(), |total, interval| {
+            total + interval.duration(now)
+        })
+    }
+}
+
+/// # Parse Due Date
+/// Parses a due date as it is written to the data file, either a moment in
+/// time or a day on its own for items that are due all day
+///
+/// ## Args
+///   - due: the serialized due date
+///   - zone: the timezone the day of an item due all day is in
+///
+/// ## Rets
+///   - Result with the date in the local timezone and whether it is all day,
+///     or a parse error
+pub(crate) fn parse_due(due: &str, zone: Zone) -> Result<(DateTime<Local>, bool), Error> {
+    if let Ok(day) = NaiveDate::parse_from_str(due, DAY_FORMAT) {
+        return Ok((end_of_day(day, zone), true));
+    }
+    DateTime::parse_from_str(due, "%Y-%m-%d %H:%M:%S%.f %:z")
+        .map(|due| (due.with_timezone(&Local), false))
+        .map_err(|e| Error::Parse(format!("Invalid due date '{}'. {}", due, e)))
+}
+
+/// # Format Timestamp
+/// Formats a moment in time as it is written in attributes (RFC 3339 in UTC)
+pub(crate) fn format_timestamp(timestamp: DateTime<Local>) -> String {
+    timestamp
+        .with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// # Parse Timestamp
+/// Parses a moment in time as it is written in attributes (RFC 3339)
+pub(crate) fn parse_timestamp(timestamp: &str) -> Result<DateTime<Local>, Error> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|timestamp| timestamp.with_timezone(&Local))
+        .map_err(|_| Error::Parse(format!("Invalid timestamp '{}'", timestamp)))
 }
@@ -0,0 +1,493 @@
+use chrono::{DateTime, Local};
+use chrono_tz::Tz;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::filter::Filter;
+use crate::migration::{self, Upgrade, CURRENT_VERSION};
+use crate::priority::PriorityScheme;
+use crate::selector::Selector;
+use crate::sort::{self, SortSpec};
+use crate::time_tracking::TimeInterval;
+use crate::todo_item::TodoItem;
+use crate::urgency::Coefficients;
+
+/// # Todo List Object
+/// A collection of todo items backed by a data file.
+///
+/// ## Data Members
+///   - path: location of the data file
+///   - items: the todo items that parsed successfully
+///   - invalid: lines that couldn't be parsed, with their line number in
+///     the saved file. These are kept so that saving the list never loses
+///     data.
+///   - upgrade: the format upgrade applied when the list was opened
+///   - scheme: how the priorities of the list are written
+#[derive(Debug)]
+pub struct TodoList {
+    path: PathBuf,
+    items: Vec<TodoItem>,
+    invalid: Vec<(usize, String)>,
+    upgrade: Option<Upgrade>,
+    scheme: PriorityScheme,
+}
+
+impl TodoList {
+    /// # Default Path
+    /// Gets the default data file location, $CONFIG/todo/todo. Uses the dirs
+    /// crate to find the configuration dir.
+    pub fn default_path() -> Result<PathBuf> {
+        match dirs::config_dir() {
+            Some(mut path) => {
+                path.push("todo");
+                path.push("todo");
+                Ok(path)
+            }
+            None => Err(Error::NoConfigDir),
+        }
+    }
+
+    /// # Open Default List
+    /// Opens the todo list stored at the default location
+    pub fn open_default() -> Result<TodoList> {
+        TodoList::open(TodoList::default_path()?)
+    }
+
+    /// # Open
+    /// Reads the todo list stored in the given file. If the file doesn't
+    /// exist then it, and all of its parents, are created. Files in an older
+    /// format are backed up and upgraded to the current format, and files
+    /// from a newer version are refused.
+    ///
+    /// ## Args
+    ///   - path: location of the data file
+    ///
+    /// ## Rets
+    ///   - Result with the todo list or an error
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<TodoList> {
+        let path = path.into();
+
+        // The path object itself makes no assumptions about the existance
+        // of the file so create it if it isn't there
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut data_file = File::create(&path)?;
+            writeln!(
+                data_file,
+                "{}",
+                migration::header(PriorityScheme::default())
+            )?;
+        }
+
+        let mut lines = io::BufReader::new(File::open(&path)?)
+            .lines()
+            .collect::<io::Result<Vec<String>>>()?;
+
+        // An empty file has nothing to upgrade so it is treated as current
+        let version = if lines.is_empty() {
+            CURRENT_VERSION
+        } else {
+            migration::detect_version(lines.first().map(String::as_str))?
+        };
+        if version > CURRENT_VERSION {
+            return Err(Error::UnsupportedVersion {
+                found: version,
+                supported: CURRENT_VERSION,
+            });
+        }
+
+        let scheme = migration::detect_scheme(lines.first().map(String::as_str))?;
+
+        // Unversioned and empty files have no header line to skip
+        let has_header = lines.first().is_some_and(|line| migration::is_header(line));
+        if has_header {
+            lines.remove(0);
+        }
+
+        let mut upgrade = None;
+        if version < CURRENT_VERSION {
+            let backup = migration::backup(&path, &format!("v{}", version))?;
+            lines = migration::migrate(lines, version)?;
+            upgrade = Some(Upgrade {
+                from: version,
+                to: CURRENT_VERSION,
+                backup,
+            });
+        }
+
+        let mut list = TodoList {
+            path,
+            items: Vec::new(),
+            invalid: Vec::new(),
+            upgrade,
+            scheme,
+        };
+
+        // Parse each todo item into the todo items list. Lines that can't be
+        // parsed are set aside rather than dropped. Their line numbers count
+        // the header, which is written on every save even if the file
+        // didn't have one yet
+        for (number, line) in lines.into_iter().enumerate() {
+            match line.parse::<TodoItem>() {
+                Ok(item) => list.items.push(item),
+                Err(_) => list.invalid.push((number + 2, line)),
+            }
+        }
+
+        // Write the upgraded list back so it only has to be upgraded once
+        if list.upgrade.is_some() {
+            list.save()?;
+        }
+
+        Ok(list)
+    }
+
+    /// # Save
+    /// Writes the whole todo list back to its data file. Lines that couldn't
+    /// be parsed are put back on the line they were read from, so saving
+    /// never moves them around the file.
+    pub fn save(&self) -> Result<()> {
+        let mut lines = vec![migration::header(self.scheme)];
+        let mut invalid = self.invalid.iter().peekable();
+        for item in &self.items {
+            while let Some((_, line)) = invalid.next_if(|(number, _)| *number <= lines.len() + 1) {
+                lines.push(line.clone());
+            }
+            lines.push(item.to_string());
+        }
+        // Any left over were after the last item
+        lines.extend(invalid.map(|(_, line)| line.clone()));
+        write_lines(&self.path, &lines)
+    }
+
+    /// Location of the data file backing this list
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All of the todo items in the list
+    pub fn items(&self) -> &[TodoItem] {
+        &self.items
+    }
+
+    /// Lines of the data file that couldn't be parsed, with their line number
+    pub fn invalid_lines(&self) -> &[(usize, String)] {
+        &self.invalid
+    }
+
+    /// The format upgrade applied when the list was opened, if there was one
+    pub fn upgrade(&self) -> Option<&Upgrade> {
+        self.upgrade.as_ref()
+    }
+
+    /// How the priorities of the list are written
+    pub fn scheme(&self) -> PriorityScheme {
+        self.scheme
+    }
+
+    /// # Set Priority Scheme
+    /// Switches the list to another priority scheme, moving every priority
+    /// to the level of the same importance in the new scheme. Schemes with
+    /// fewer levels can't tell some priorities apart so the conversion can
+    /// lose detail. The list has to be saved for the change to be written.
+    ///
+    /// ## Args
+    ///   - scheme: the scheme to switch to
+    ///
+    /// ## Rets
+    ///   - the number of items whose priority was converted
+    pub fn set_scheme(&mut self, scheme: PriorityScheme) -> usize {
+        let mut converted = 0;
+        for item in &mut self.items {
+            if let Some(priority) = item.priority {
+                item.priority = Some(self.scheme.convert(priority, scheme));
+                converted += 1;
+            }
+        }
+        self.scheme = scheme;
+        converted
+    }
+
+    /// Number of todo items in the list
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the list has no todo items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// # Get
+    /// Gets the todo item with the given id
+    pub fn get(&self, id: u32) -> Option<&TodoItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    /// # Get Mutable
+    /// Gets the todo item with the given id so that it can be changed
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut TodoItem> {
+        self.items.iter_mut().find(|item| item.id == id)
+    }
+
+    /// # Add
+    /// Adds a todo item to the list, giving it the next free id and marking
+    /// when it was created if that isn't already set. The list has to be
+    /// saved for the item to be written to the data file.
+    ///
+    /// ## Rets
+    ///   - the id given to the item
+    pub fn add(&mut self, mut item: TodoItem) -> u32 {
+        item.id = self.next_id();
+        if item.created.is_none() {
+            item.created = Some(Local::now());
+        }
+        self.items.push(item);
+        self.items[self.items.len() - 1].id
+    }
+
+    /// # Next Id
+    /// Gets the id that the next item added to the list will be given. Ids
+    /// of archived items are never given out again.
+    pub fn next_id(&self) -> u32 {
+        let newest = self.items.iter().map(|item| item.id).max().unwrap_or(0);
+        newest.max(archived_max_id(&self.archive_path())) + 1
+    }
+
+    /// # Archive Path
+    /// Gets the location of the archive file that done items are moved to,
+    /// the data file with `.archive` added (eg. todo.archive)
+    pub fn archive_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".archive");
+        PathBuf::from(path)
+    }
+
+    /// # Open Archive
+    /// Reads the archived items of this list as a list of their own
+    pub fn open_archive(&self) -> Result<TodoList> {
+        TodoList::open(self.archive_path())
+    }
+
+    /// # Archive
+    /// Moves the items completed before a time to the archive file and saves
+    /// both files. The archive is written first so that a failed save leaves
+    /// items in both files rather than in neither.
+    ///
+    /// ## Args
+    ///   - before: archive items completed before this time
+    ///
+    /// ## Rets
+    ///   - Result with the items that were archived
+    pub fn archive(&mut self, before: DateTime<Local>) -> Result<Vec<TodoItem>> {
+        let (archived, kept): (Vec<TodoItem>, Vec<TodoItem>) = self
+            .items
+            .drain(..)
+            .partition(|item| item.completed.is_some_and(|completed| completed < before));
+        self.items = kept;
+
+        if !archived.is_empty() {
+            // The archive keeps its own scheme so priorities are converted
+            // into it
+            let mut archive = self.open_archive()?;
+            let (from, to) = (self.scheme, archive.scheme);
+            archive
+                .items
+                .extend(archived.iter().cloned().map(|mut item| {
+                    item.priority = item.priority.map(|priority| from.convert(priority, to));
+                    item
+                }));
+            archive.save()?;
+            self.save()?;
+        }
+
+        Ok(archived)
+    }
+
+    /// # Start Tracking Time
+    /// Starts tracking time against an item. Only one item can be worked on
+    /// at a time so any other running interval is stopped first.
+    ///
+    /// ## Args
+    ///   - id: the item to start working on
+    ///   - now: the current time
+    ///
+    /// ## Rets
+    ///   - Result with the id of the item that was stopped, if there was one
+    pub fn start(&mut self, id: u32, now: DateTime<Local>) -> Result<Option<u32>> {
+        if self.get(id).is_none() {
+            return Err(Error::NoSuchItem(id));
+        }
+
+        // Starting the item that is already running changes nothing
+        if let Some((active, _)) = self.active() {
+            if active.id == id {
+                return Ok(None);
+            }
+        }
+
+        let stopped = self.stop(now).map(|(stopped, _)| stopped);
+        if let Some(item) = self.get_mut(id) {
+            item.time.push(TimeInterval {
+                start: now,
+                end: None,
+            });
+        }
+
+        Ok(stopped)
+    }
+
+    /// # Stop Tracking Time
+    /// Stops the running time interval, if there is one
+    ///
+    /// ## Args
+    ///   - now: the current time
+    ///
+    /// ## Rets
+    ///   - the id of the item that was stopped and the interval that ended
+    pub fn stop(&mut self, now: DateTime<Local>) -> Option<(u32, TimeInterval)> {
+        for item in &mut self.items {
+            if let Some(interval) = item.time.iter_mut().find(|i| i.is_running()) {
+                interval.end = Some(now);
+                return Some((item.id, interval.clone()));
+            }
+        }
+        None
+    }
+
+    /// # Active Item
+    /// Gets the item that time is currently being tracked against, along
+    /// with its running interval
+    pub fn active(&self) -> Option<(&TodoItem, &TimeInterval)> {
+        self.items
+            .iter()
+            .find_map(|item| item.running_interval().map(|interval| (item, interval)))
+    }
+
+    /// # Complete
+    /// Marks an item as done. Time stops being tracked against it if it was
+    /// running.
+    ///
+    /// ## Args
+    ///   - id: the item that was done
+    ///   - now: the current time
+    pub fn complete(&mut self, id: u32, now: DateTime<Local>) -> Result<()> {
+        let item = self.get_mut(id).ok_or(Error::NoSuchItem(id))?;
+        if let Some(interval) = item.time.iter_mut().find(|i| i.is_running()) {
+            interval.end = Some(now);
+        }
+        if item.completed.is_none() {
+            item.completed = Some(now);
+        }
+        Ok(())
+    }
+
+    /// # Reschedule
+    /// Moves the due date of an item
+    ///
+    /// ## Args
+    ///   - id: the item to move
+    ///   - due: the new due date
+    ///   - all_day: whether the item is now due all day
+    ///   - tz: the timezone the new due date was set in
+    ///
+    /// ## Rets
+    ///   - the due date the item had before, if it had one
+    pub fn reschedule(
+        &mut self,
+        id: u32,
+        due: DateTime<Local>,
+        all_day: bool,
+        tz: Option<Tz>,
+    ) -> Result<Option<DateTime<Local>>> {
+        let item = self.get_mut(id).ok_or(Error::NoSuchItem(id))?;
+        item.all_day = all_day;
+        item.tz = tz;
+        Ok(item.due.replace(due))
+    }
+
+    /// # Sort
+    /// Sorts the items in the list by the spec
+    ///
+    /// ## Args
+    ///   - spec: how to sort the items
+    ///   - coefficients: urgency weights, used if the spec sorts by urgency
+    ///   - now: the current time
+    pub fn sort(&mut self, spec: &SortSpec, coefficients: &Coefficients, now: DateTime<Local>) {
+        sort::sort(&mut self.items, spec, coefficients, self.scheme, now);
+    }
+
+    /// # Filter
+    /// Gets all of the items in the list that match the filter
+    pub fn filter(&self, filter: &Filter) -> Vec<&TodoItem> {
+        self.items
+            .iter()
+            .filter(|item| filter.matches(item))
+            .collect()
+    }
+
+    /// # Select
+    /// Gets all of the items in the list that the selector picks out
+    ///
+    /// ## Args
+    ///   - selector: which items to get
+    ///   - now: the current time
+    ///
+    /// ## Rets
+    ///   - Result with the items or an error if the selector names an id
+    ///     that isn't in the list
+    pub fn select(&self, selector: &Selector, now: DateTime<Local>) -> Result<Vec<&TodoItem>> {
+        if let Some(id) = selector.ids.iter().find(|id| self.get(**id).is_none()) {
+            return Err(Error::NoSuchItem(*id));
+        }
+        Ok(self
+            .items
+            .iter()
+            .filter(|item| selector.matches(item, now))
+            .collect())
+    }
+}
+
+/// # Archived Max Id
+/// Gets the largest id in an archive file. The archive is only read to keep
+/// ids from being reused, so a missing or unreadable archive counts as empty.
+fn archived_max_id(path: &Path) -> u32 {
+    fs::read_to_string(path)
+        .map(|text| {
+            text.lines()
+                .filter_map(|line| line.parse::<TodoItem>().ok())
+                .map(|item| item.id)
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0)
+}
+
+/// # Write Lines
+/// Replaces the contents of a file with the given lines. The lines are
+/// written to a temporary file first and then moved over the file so that a
+/// failed write can't leave a half written file behind.
+///
+/// ## Args
+///   - path: location of the file
+///   - lines: the lines to write
+pub(crate) fn write_lines(path: &Path, lines: &[String]) -> Result<()> {
+    let mut temp_path = path.to_path_buf().into_os_string();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    {
+        let mut temp_file = io::BufWriter::new(File::create(&temp_path)?);
+        for line in lines {
+            writeln!(temp_file, "{}", line)?;
+        }
+        temp_file.flush()?;
+    }
+
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, HashSet};
+
+use crate::priority::PriorityScheme;
+use crate::todo_item::TodoItem;
+
+/// # Urgency Coefficients
+/// Weights for each part of the urgency score. They can be changed in the
+/// config file with `urgency.<name> = <number>` settings, and any tag can be
+/// given its own weight with `urgency.tag.<name> = <number>`.
+///
+/// ## Data Members
+///   - priority: weight of an item with the most important priority. Less
+///     important priorities get a proportionally smaller share down to
+///     nothing for the least important priority of the scheme
+///   - due: weight of an item that is due now. The share grows from a fifth
+///     two weeks before the due date up to all of it a week after
+///   - overdue: added to items that are past their due date
+///   - age: weight of an item that is a year or more old, scaled by age
+///   - blocking: added to items that other items depend on
+///   - blocked: added to items that depend on unfinished items
+///   - tags: added to items with any +project or @context
+///   - tag: extra weight for items with a particular project or context
+#[derive(Clone, Debug)]
+pub struct Coefficients {
+    pub priority: f64,
+    pub due: f64,
+    pub overdue: f64,
+    pub age: f64,
+    pub blocking: f64,
+    pub blocked: f64,
+    pub tags: f64,
+    pub tag: HashMap<String, f64>,
+}
+
+impl Default for Coefficients {
+    fn default() -> Coefficients {
+        Coefficients {
+            priority: 6.0,
+            due: 12.0,
+            overdue: 4.0,
+            age: 2.0,
+            blocking: 8.0,
+            blocked: -5.0,
+            tags: 1.0,
+            tag: HashMap::new(),
+        }
+    }
+}
+
+/// # Urgency Scorer
+/// Scores the urgency of todo items in a list. Whether an item is blocking
+/// or blocked depends on the rest of the list, so that is worked out once up
+/// front.
+pub struct Scorer<'a> {
+    coefficients: &'a Coefficients,
+    scheme: PriorityScheme,
+    now: DateTime<Local>,
+    blocking: HashSet<u32>,
+    blocked: HashSet<u32>,
+}
+
+impl<'a> Scorer<'a> {
+    /// # New Scorer
+    ///
+    /// ## Args
+    ///   - items: every item in the list
+    ///   - coefficients: weights of each part of the score
+    ///   - scheme: how the priorities of the list are written
+    ///   - now: the current time
+    pub fn new(
+        items: &[TodoItem],
+        coefficients: &'a Coefficients,
+        scheme: PriorityScheme,
+        now: DateTime<Local>,
+    ) -> Scorer<'a> {
+        let open: HashSet<u32> = items
+            .iter()
+            .filter(|item| !item.is_done())
+            .map(|item| item.id)
+            .collect();
+
+        let mut blocking = HashSet::new();
+        let mut blocked = HashSet::new();
+        for item in items.iter().filter(|item| !item.is_done()) {
+            for depends in item.depends.iter().filter(|id| open.contains(id)) {
+                blocking.insert(*depends);
+                blocked.insert(item.id);
+            }
+        }
+
+        Scorer {
+            coefficients,
+            scheme,
+            now,
+            blocking,
+            blocked,
+        }
+    }
+
+    /// # Score
+    /// Gets the urgency of an item. Higher is more urgent.
+    pub fn score(&self, item: &TodoItem) -> f64 {
+        let c = self.coefficients;
+        let mut score = 0.0;
+
+        if let Some(priority) = item.priority {
+            score += c.priority * self.scheme.importance(priority);
+        }
+
+        if let Some(due) = item.due {
+            let days_overdue = (self.now - due).num_seconds() as f64 / 86400.0;
+            let share = if days_overdue >= 7.0 {
+                1.0
+            } else if days_overdue >= -14.0 {
+                (days_overdue + 14.0) * 0.8 / 21.0 + 0.2
+            } else {
+                0.2
+            };
+            score += c.due * share;
+
+            if days_overdue > 0.0 {
+                score += c.overdue;
+            }
+        }
+
+        if let Some(created) = item.created {
+            let days_old = (self.now - created).num_days() as f64;
+            score += c.age * (days_old / 365.0).clamp(0.0, 1.0);
+        }
+
+        if self.blocking.contains(&item.id) {
+            score += c.blocking;
+        }
+        if self.blocked.contains(&item.id) {
+            score += c.blocked;
+        }
+
+        let tags: Vec<&str> = item.projects().into_iter().chain(item.contexts()).collect();
+        if !tags.is_empty() {
+            score += c.tags;
+        }
+        for tag in tags {
+            score += c.tag.get(tag).copied().unwrap_or(0.0);
+        }
+
+        score
+    }
+}
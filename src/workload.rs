@@ -0,0 +1,174 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+use crate::error::{Error, Result};
+use crate::todo_item::TodoItem;
+use crate::zone::Zone;
+
+/// # Day Load
+/// The work planned for a single day.
+///
+/// ## Data Members
+///   - date: the day
+///   - planned: total estimated work planned for the day
+///   - items: ids of the items with work planned for the day
+#[derive(Clone, Debug)]
+pub struct DayLoad {
+    pub date: NaiveDate,
+    pub planned: Duration,
+    pub items: Vec<u32>,
+}
+
+/// # Workload Forecast
+/// Forecast of the estimated work left on items that aren't done. Each
+/// item's estimate is spread evenly over the days from today up to and
+/// including its due date, so the planned work for a day is the share of
+/// every item that has to be worked on that day to finish on time. Overdue
+/// items land entirely on today.
+///
+/// ## Data Members
+///   - days: planned work for each day from today onwards
+///   - unscheduled: estimated work on items without a due date
+///   - capacity: how much work fits in a day
+#[derive(Clone, Debug)]
+pub struct Workload {
+    pub days: Vec<DayLoad>,
+    pub unscheduled: Duration,
+    pub capacity: Duration,
+}
+
+impl Workload {
+    /// # New Workload
+    /// Forecasts the workload of the given items
+    ///
+    /// ## Args
+    ///   - items: the todo items to forecast
+    ///   - today: the first day of the forecast
+    ///   - days: how many days to forecast
+    ///   - capacity: how much work fits in a day
+    ///   - zone: the timezone days are counted in
+    pub fn new(
+        items: &[TodoItem],
+        today: NaiveDate,
+        days: u32,
+        capacity: Duration,
+        zone: Zone,
+    ) -> Workload {
+        let mut workload = Workload {
+            days: (0..days)
+                .map(|offset| DayLoad {
+                    date: today + Duration::days(i64::from(offset)),
+                    planned: Duration::zero(),
+                    items: Vec::new(),
+                })
+                .collect(),
+            unscheduled: Duration::zero(),
+            capacity,
+        };
+
+        for item in items.iter().filter(|item| !item.is_done()) {
+            let estimate = match item.estimate {
+                Some(estimate) => estimate,
+                None => continue,
+            };
+
+            let due = match item.due {
+                Some(due) => item.due_zone(zone).at(due).date_naive(),
+                None => {
+                    workload.unscheduled += estimate;
+                    continue;
+                }
+            };
+
+            // Spread the estimate a minute at a time so that no work is lost
+            // to rounding. Earlier days get any leftover minutes
+            let span = (due - today).num_days().max(0) + 1;
+            let minutes = estimate.num_minutes();
+            for (offset, day) in workload.days.iter_mut().enumerate() {
+                let offset = offset as i64;
+                if offset >= span {
+                    break;
+                }
+
+                let share = minutes / span + if offset < minutes % span { 1 } else { 0 };
+                if share > 0 {
+                    day.planned += Duration::minutes(share);
+                    day.items.push(item.id);
+                }
+            }
+        }
+
+        workload
+    }
+
+    /// # Overloaded Days
+    /// Gets the days with more work planned than fits in a day
+    pub fn overloaded(&self) -> Vec<&DayLoad> {
+        self.days
+            .iter()
+            .filter(|day| day.planned > self.capacity)
+            .collect()
+    }
+
+    /// # Workload by Week
+    /// Gets the planned work for each week of the forecast. Weeks start on
+    /// Monday and are identified by that date.
+    pub fn by_week(&self) -> Vec<(NaiveDate, Duration)> {
+        let mut weeks: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+        for day in &self.days {
+            let monday =
+                day.date - Duration::days(i64::from(day.date.weekday().num_days_from_monday()));
+            *weeks.entry(monday).or_insert_with(Duration::zero) += day.planned;
+        }
+        weeks.into_iter().collect()
+    }
+}
+
+/// # Parse Estimate
+/// Parses an amount of work written as hours and minutes, eg. "2h", "45m",
+/// "1h30m" or "1.5h". A bare number is taken to be hours.
+///
+/// ## Args
+///   - estimate: the estimate string
+///
+/// ## Rets
+///   - Result with the estimate or a parse error
+pub fn parse_estimate(estimate: &str) -> Result<Duration> {
+    lazy_static! {
+        static ref ESTIMATE_RE: Regex =
+            Regex::new(r"^(?:(\d+(?:\.\d+)?)h)?\s*(?:(\d+)m)?$|^(\d+(?:\.\d+)?)$").unwrap();
+    }
+
+    let invalid = || {
+        Error::Parse(format!(
+            "Invalid estimate '{}'. Use hours and minutes like 2h, 45m or 1h30m.",
+            estimate
+        ))
+    };
+
+    let estimate = estimate.trim().to_lowercase();
+    let cap = match ESTIMATE_RE.captures(&estimate) {
+        Some(cap) if !estimate.is_empty() => cap,
+        _ => return Err(invalid()),
+    };
+
+    let hours = cap
+        .get(1)
+        .or_else(|| cap.get(3))
+        .map_or(Ok(0.0), |hours| hours.as_str().parse::<f64>())
+        .map_err(|_| invalid())?;
+    let minutes = cap
+        .get(2)
+        .map_or(Ok(0), |minutes| minutes.as_str().parse::<i64>())
+        .map_err(|_| invalid())?;
+
+    // Casting saturates, so anything at or past the largest i64 is too long
+    // rather than quietly clamped
+    let hour_minutes = (hours * 60.0).round();
+    Some(hour_minutes)
+        .filter(|hour_minutes| *hour_minutes < i64::MAX as f64)
+        .and_then(|hour_minutes| (hour_minutes as i64).checked_add(minutes))
+        .and_then(Duration::try_minutes)
+        .ok_or_else(|| Error::Parse(format!("Estimate '{}' is too long", estimate)))
+}
@@ -0,0 +1,103 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// # Zone
+/// The timezone that dates are typed and shown in. Dates are always stored
+/// as instants in UTC so they mean the same thing wherever they are read.
+///
+///   - Local: the timezone of the computer
+///   - Named: a timezone from the IANA database, eg. "America/New_York"
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Zone {
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+// FromStr trait implementation so the zone can be given in the config file
+// or on the command line
+impl FromStr for Zone {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Zone, Error> {
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        s.parse::<Tz>().map(Zone::Named).map_err(|_| {
+            Error::Parse(format!(
+                "Unknown timezone '{}'. Expected local or a name like America/New_York or UTC",
+                s
+            ))
+        })
+    }
+}
+
+// Display trait implementation that writes the zone name back out
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Local => write!(f, "local"),
+            Zone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+// From trait implementation for the timezone recorded with a due date,
+// which is the local one if none was
+impl From<Option<Tz>> for Zone {
+    fn from(tz: Option<Tz>) -> Zone {
+        tz.map_or(Zone::Local, Zone::Named)
+    }
+}
+
+impl Zone {
+    /// # Named Zone
+    /// Gets the IANA timezone, looking up the timezone of the computer for
+    /// the local zone. None if the computer's timezone can't be found.
+    pub fn named(self) -> Option<Tz> {
+        match self {
+            Zone::Named(tz) => Some(tz),
+            Zone::Local => iana_time_zone::get_timezone().ok()?.parse::<Tz>().ok(),
+        }
+    }
+
+    /// # At
+    /// Gets an instant as it is on the clock in this zone, for showing it
+    pub fn at(self, instant: DateTime<Local>) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Local => instant.fixed_offset(),
+            Zone::Named(tz) => instant.with_timezone(&tz).fixed_offset(),
+        }
+    }
+
+    /// # Localize
+    /// Gets the instant that a time on the clock in this zone refers to.
+    /// Times that are skipped when the clocks go forward are moved on past
+    /// the gap, and times that happen twice when the clocks go back are the
+    /// earlier of the two.
+    pub fn localize(self, time: NaiveDateTime) -> DateTime<Local> {
+        match self {
+            Zone::Local => localize_in(&Local, time),
+            Zone::Named(tz) => localize_in(&tz, time),
+        }
+    }
+}
+
+/// Gets the instant a time on the clock refers to in any timezone. Gaps are
+/// never longer than a couple of hours so trying a few hours later always
+/// finds a time that exists.
+fn localize_in<Z: TimeZone>(zone: &Z, time: NaiveDateTime) -> DateTime<Local> {
+    (0..3)
+        .find_map(|hour| {
+            zone.from_local_datetime(&(time + chrono::Duration::hours(hour)))
+                .earliest()
+        })
+        .map_or_else(
+            || Utc.from_utc_datetime(&time).with_timezone(&Local),
+            |instant| instant.with_timezone(&Local),
+        )
+}
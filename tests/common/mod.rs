@@ -0,0 +1,11 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Gets a fresh directory for a test to keep its data files in. Names have
+/// to be unique across every test since the tests run at the same time
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("to_done-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,31 @@
+use std::fs;
+use to_done::doctor::{self, Problem, Repair};
+
+mod common;
+use common::scratch_dir;
+
+#[test]
+fn duplicate_ids_are_renumbered_past_the_archive() {
+    let dir = scratch_dir("doctor-duplicate");
+    let path = dir.join("todo");
+    fs::write(
+        &path,
+        "# todo-format: 4\n[]{} First\tid=1\n[]{} Second\tid=1\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("todo.archive"),
+        "# todo-format: 4\n[]{} Archived\tid=2\n",
+    )
+    .unwrap();
+
+    let diagnoses = doctor::examine(&path).unwrap();
+    assert_eq!(diagnoses.len(), 1);
+    assert_eq!(diagnoses[0].problems, vec![Problem::DuplicateId(1)]);
+    match &diagnoses[0].repair {
+        Repair::Replace(item) => assert_eq!(item.id, 3),
+        Repair::Quarantine => panic!("the line should be replaced"),
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
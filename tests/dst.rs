@@ -0,0 +1,238 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use chrono_tz::America::New_York;
+use chrono_tz::Asia::Tokyo;
+use to_done::calendar::{Calendar, Span};
+use to_done::dates::{end_of_day, parse_date, parse_due, DateDialect};
+use to_done::timesheet::Timesheet;
+use to_done::workload::Workload;
+use to_done::{DueBand, TimeInterval, TodoItem, Zone};
+
+const ZONE: Zone = Zone::Named(New_York);
+
+/// Gets the instant a time on the clock in New York refers to
+fn new_york(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    New_York
+        .with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .earliest()
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+/// Parses a date in New York the way the command line does
+fn parse(text: &str, base: DateTime<Local>) -> DateTime<Local> {
+    parse_date(text, base, DateDialect::Us, ZONE).unwrap()
+}
+
+/// Gets the hour and minute on the clock in New York
+fn clock(date: DateTime<Local>) -> (u32, u32) {
+    let date = date.with_timezone(&New_York);
+    (date.hour(), date.minute())
+}
+
+#[test]
+fn day_offset_keeps_time_across_spring_forward() {
+    // The clocks go forward at 2am on March 8th 2026
+    let base = new_york(2026, 3, 7, 9, 0);
+    let due = parse("1d", base);
+    assert_eq!(due, new_york(2026, 3, 8, 9, 0));
+    assert_eq!(due - base, Duration::hours(23));
+}
+
+#[test]
+fn hour_offset_is_exact_across_spring_forward() {
+    let base = new_york(2026, 3, 7, 9, 0);
+    let due = parse("24h", base);
+    assert_eq!(due - base, Duration::hours(24));
+    assert_eq!(clock(due), (10, 0));
+}
+
+#[test]
+fn day_offset_keeps_time_across_fall_back() {
+    // The clocks go back at 2am on November 1st 2026
+    let base = new_york(2026, 10, 31, 9, 0);
+    let due = parse("1d", base);
+    assert_eq!(due, new_york(2026, 11, 1, 9, 0));
+    assert_eq!(due - base, Duration::hours(25));
+}
+
+#[test]
+fn tomorrow_at_a_time_across_spring_forward() {
+    let base = new_york(2026, 3, 7, 22, 0);
+    let due = parse("tomorrow 9am", base);
+    assert_eq!(due, new_york(2026, 3, 8, 9, 0));
+}
+
+#[test]
+fn time_in_the_gap_moves_past_it() {
+    // 2:30am doesn't exist on March 8th 2026 so it becomes 3:30am
+    let base = new_york(2026, 3, 1, 9, 0);
+    let due = parse("2026-03-08 02:30", base);
+    assert_eq!(due, new_york(2026, 3, 8, 3, 30));
+}
+
+#[test]
+fn all_day_due_is_the_day_in_the_zone() {
+    let base = new_york(2026, 3, 7, 22, 0);
+    let (due, all_day) = parse_due("tomorrow", base, DateDialect::Us, ZONE).unwrap();
+    assert!(all_day);
+    // The day ends at midnight in New York whatever timezone this runs in
+    assert_eq!(due, new_york(2026, 3, 9, 0, 0) - Duration::nanoseconds(1));
+}
+
+#[test]
+fn all_day_item_keeps_its_day_in_its_zone() {
+    let day = NaiveDate::from_ymd_opt(2026, 3, 8).unwrap();
+    let item = TodoItem {
+        id: 1,
+        item: String::from("Pay rent"),
+        due: Some(end_of_day(day, Zone::Named(Tokyo))),
+        all_day: true,
+        tz: Some(Tokyo),
+        ..TodoItem::default()
+    };
+
+    let line = item.to_string();
+    assert!(line.contains("{2026-03-08}"), "{}", line);
+    let read: TodoItem = line.parse().unwrap();
+    assert!(read.all_day);
+    assert_eq!(read.due, item.due);
+}
+
+#[test]
+fn timesheet_days_are_in_the_zone() {
+    // An hour either side of midnight in New York
+    let item = TodoItem {
+        id: 1,
+        item: String::from("Late shift"),
+        time: vec![TimeInterval {
+            start: new_york(2026, 3, 7, 23, 0),
+            end: Some(new_york(2026, 3, 8, 1, 0)),
+        }],
+        ..TodoItem::default()
+    };
+
+    let from = NaiveDate::from_ymd_opt(2026, 3, 7).unwrap();
+    let to = NaiveDate::from_ymd_opt(2026, 3, 8).unwrap();
+    let sheet = Timesheet::new(&[item], from, to, new_york(2026, 3, 9, 0, 0), ZONE);
+    let days = sheet
+        .entries
+        .iter()
+        .map(|entry| (entry.date.to_string(), entry.duration))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        days,
+        vec![
+            (String::from("2026-03-07"), Duration::hours(1)),
+            (String::from("2026-03-08"), Duration::hours(1)),
+        ]
+    );
+}
+
+#[test]
+fn repeated_day_shifts_keep_time_over_a_dst_week() {
+    // Moving a daily item on one day at a time through the change
+    let mut due = new_york(2026, 3, 5, 9, 0);
+    for _ in 0..7 {
+        due = parse("1d", due);
+        assert_eq!(clock(due), (9, 0));
+    }
+    assert_eq!(due, new_york(2026, 3, 12, 9, 0));
+}
+
+#[test]
+fn item_is_stored_in_utc_with_its_zone() {
+    let item = TodoItem {
+        id: 1,
+        item: String::from("Call the bank"),
+        due: Some(new_york(2026, 3, 8, 9, 0)),
+        tz: Some(New_York),
+        ..TodoItem::default()
+    };
+
+    let line = item.to_string();
+    assert!(
+        line.contains("{2026-03-08 13:00:00.000000000 +00:00}"),
+        "{}",
+        line
+    );
+    assert!(line.contains("\ttz=America/New_York"), "{}", line);
+
+    let read: TodoItem = line.parse().unwrap();
+    assert_eq!(read.due, item.due);
+    assert_eq!(read.tz, Some(New_York));
+    assert_eq!(
+        read.due.unwrap().with_timezone(&Utc),
+        Utc.with_ymd_and_hms(2026, 3, 8, 13, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn due_band_counts_days_in_the_zone() {
+    // Half an hour after midnight in New York is tomorrow there, whatever
+    // day it is where this runs
+    let now = new_york(2026, 3, 7, 23, 0);
+    let due = new_york(2026, 3, 8, 0, 30);
+    assert_eq!(DueBand::of(due, now, ZONE), DueBand::Tomorrow);
+    assert_eq!(DueBand::of(now, now, ZONE), DueBand::Today);
+}
+
+#[test]
+fn calendar_places_items_on_their_day_in_the_zone() {
+    let late = TodoItem {
+        id: 1,
+        item: String::from("Late call"),
+        due: Some(new_york(2026, 3, 7, 23, 30)),
+        tz: Some(New_York),
+        ..TodoItem::default()
+    };
+    let rent = TodoItem {
+        id: 2,
+        item: String::from("Pay rent"),
+        due: Some(end_of_day(
+            NaiveDate::from_ymd_opt(2026, 3, 8).unwrap(),
+            Zone::Named(Tokyo),
+        )),
+        all_day: true,
+        tz: Some(Tokyo),
+        ..TodoItem::default()
+    };
+
+    let items = [late, rent];
+    let anchor = NaiveDate::from_ymd_opt(2026, 3, 7).unwrap();
+    let calendar = Calendar::new(&items, Span::Week, anchor, 0, ZONE);
+    let ids_on = |day: u32| {
+        let date = NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+        calendar.weeks[0]
+            .iter()
+            .find(|d| d.date == date)
+            .unwrap()
+            .items
+            .iter()
+            .map(|item| item.id)
+            .collect::<Vec<u32>>()
+    };
+    assert_eq!(ids_on(7), vec![1]);
+    assert_eq!(ids_on(8), vec![2]);
+}
+
+#[test]
+fn workload_counts_days_in_the_zone() {
+    // Due just after midnight in New York, so it is spread over two days
+    let item = TodoItem {
+        id: 1,
+        item: String::from("Write the report"),
+        due: Some(new_york(2026, 3, 8, 0, 30)),
+        tz: Some(New_York),
+        estimate: Some(Duration::hours(2)),
+        ..TodoItem::default()
+    };
+
+    let today = NaiveDate::from_ymd_opt(2026, 3, 7).unwrap();
+    let forecast = Workload::new(&[item], today, 2, Duration::hours(8), ZONE);
+    let planned = forecast
+        .days
+        .iter()
+        .map(|day| day.planned)
+        .collect::<Vec<_>>();
+    assert_eq!(planned, vec![Duration::hours(1), Duration::hours(1)]);
+}
@@ -0,0 +1,22 @@
+use chrono::Duration;
+use to_done::workload::parse_estimate;
+use to_done::TodoItem;
+
+#[test]
+fn estimates_parse_as_hours_and_minutes() {
+    assert_eq!(parse_estimate("1h30m").unwrap(), Duration::minutes(90));
+    assert_eq!(parse_estimate("1.5").unwrap(), Duration::minutes(90));
+}
+
+#[test]
+fn huge_estimates_are_errors() {
+    assert!(parse_estimate("999999999999999h").is_err());
+    assert!(parse_estimate("99999999999999999999h").is_err());
+    assert!(parse_estimate("9223372036854775807m").is_err());
+}
+
+#[test]
+fn huge_stored_estimates_are_errors() {
+    let line = "[]{} Item\tid=1\testimate=9999999999999999";
+    assert!(line.parse::<TodoItem>().is_err());
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Local, TimeZone};
+use chrono_tz::America::New_York;
+use to_done::dates::DateDialect;
+use to_done::{PriorityScheme, QuickAdd, Zone};
+
+const ZONE: Zone = Zone::Named(New_York);
+
+/// Splits a line the way `new --quick` does on a Sunday morning in February
+fn parse(line: &str) -> QuickAdd {
+    let now: DateTime<Local> = New_York
+        .with_ymd_and_hms(2026, 2, 1, 9, 0, 0)
+        .unwrap()
+        .with_timezone(&Local);
+    QuickAdd::parse(line, now, DateDialect::Us, ZONE, PriorityScheme::default()).unwrap()
+}
+
+#[test]
+fn trailing_date_is_the_due_date() {
+    let quick = parse("Call dentist tomorrow 3pm !2 +health @phone");
+    assert_eq!(quick.text, "Call dentist +health @phone");
+    assert_eq!(quick.due.as_deref(), Some("tomorrow 3pm"));
+    assert!(quick.priority.is_some());
+}
+
+#[test]
+fn date_after_a_marker_is_the_due_date() {
+    let quick = parse("Send the report due: friday to Sam");
+    assert_eq!(quick.text, "Send the report to Sam");
+    assert_eq!(quick.due.as_deref(), Some("friday"));
+
+    let quick = parse("Send the report due:friday to Sam");
+    assert_eq!(quick.text, "Send the report to Sam");
+    assert_eq!(quick.due.as_deref(), Some("friday"));
+}
+
+#[test]
+fn abbreviations_in_the_text_are_not_dates() {
+    let quick = parse("Call Sun about the mar report");
+    assert_eq!(quick.text, "Call Sun about the mar report");
+    assert_eq!(quick.due, None);
+}
+
+#[test]
+fn month_abbreviation_needs_a_day() {
+    let quick = parse("Book flights in mar");
+    assert_eq!(quick.due, None);
+
+    let quick = parse("Book flights by mar 5");
+    assert_eq!(quick.text, "Book flights");
+    assert_eq!(quick.due.as_deref(), Some("mar 5"));
+}
+
+#[test]
+fn numbers_and_now_are_not_dates() {
+    let quick = parse("Fix bug 1234 now");
+    assert_eq!(quick.text, "Fix bug 1234 now");
+    assert_eq!(quick.due, None);
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use to_done::{TodoItem, TodoList};
+
+mod common;
+use common::scratch_dir;
+
+#[test]
+fn empty_file_opens_as_empty_list() {
+    let dir = scratch_dir("empty");
+    let path = dir.join("todo.txt");
+    fs::write(&path, "").unwrap();
+
+    let list = TodoList::open(&path).unwrap();
+    assert!(list.is_empty());
+    assert!(list.invalid_lines().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn save_keeps_invalid_lines_in_place() {
+    let dir = scratch_dir("invalid");
+    let path = dir.join("todo.txt");
+    let contents = "# todo-format: 4\n\
+                    []{} First\tid=1\n\
+                    not an item\n\
+                    []{} Second\tid=2\n";
+    fs::write(&path, contents).unwrap();
+
+    let list = TodoList::open(&path).unwrap();
+    assert_eq!(list.invalid_lines(), &[(3, String::from("not an item"))]);
+    list.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn upgrade_keeps_invalid_lines_between_their_neighbours() {
+    let dir = scratch_dir("upgrade");
+    let path = dir.join("todo.txt");
+    fs::write(&path, "[]{} First\nnot an item\n[]{} Second\n").unwrap();
+
+    let list = TodoList::open(&path).unwrap();
+    assert_eq!(list.invalid_lines(), &[(3, String::from("not an item"))]);
+    let saved = fs::read_to_string(&path).unwrap();
+    let lines = saved.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("[]{} First"));
+    assert_eq!(lines[2], "not an item");
+    assert!(lines[3].starts_with("[]{} Second"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn line_breaks_in_text_stay_on_one_line() {
+    let dir = scratch_dir("line-breaks");
+    let path = dir.join("todo.txt");
+    fs::write(&path, "").unwrap();
+
+    let mut list = TodoList::open(&path).unwrap();
+    let id = list.add(TodoItem {
+        item: String::from("multi\nline\r\ntext"),
+        ..TodoItem::default()
+    });
+    list.save().unwrap();
+
+    let list = TodoList::open(&path).unwrap();
+    assert!(list.invalid_lines().is_empty());
+    assert_eq!(list.len(), 1);
+    let item = list.get(id).unwrap();
+    assert_eq!(item.item, "multi line  text");
+
+    fs::remove_dir_all(&dir).unwrap();
+}